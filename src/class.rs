@@ -0,0 +1,94 @@
+pub mod class {
+    use core::fmt;
+    use std::{cell::RefCell, collections::HashMap, rc::Rc};
+
+    use crate::{function::function::{Arity, Function}, object::object::Object, parser::parser::Error, token::token::Token};
+
+    #[derive(Debug)]
+    pub struct Class {
+        pub name: String,
+        superclass: Option<Rc<Class>>,
+        methods: HashMap<String, Function>,
+    }
+
+    impl Class {
+        pub fn new(name: String, superclass: Option<Rc<Class>>, methods: HashMap<String, Function>) -> Self {
+            Class { name, superclass, methods }
+        }
+
+        /// Finds a method on this class or, failing that, up the chain of
+        /// superclasses.
+        pub fn find_method(&self, name: &str) -> Option<&Function> {
+            self.methods
+                .get(name)
+                .or_else(|| self.superclass.as_ref().and_then(|superclass| superclass.find_method(name)))
+        }
+
+        pub fn superclass(&self) -> Option<&Rc<Class>> {
+            self.superclass.as_ref()
+        }
+
+        /// The methods declared on this class itself.
+        pub fn methods(&self) -> impl Iterator<Item = &Function> {
+            self.methods.values()
+        }
+
+        /// Calling a class takes the arguments of its initializer, if any.
+        pub fn arity(&self) -> Arity {
+            self.find_method("init").map(Function::arity).unwrap_or(Arity::Exact(0))
+        }
+    }
+
+    impl fmt::Display for Class {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "{}", self.name)
+        }
+    }
+
+    #[derive(Debug)]
+    pub struct Instance {
+        pub class: Rc<Class>,
+        fields: HashMap<String, Object>,
+    }
+
+    impl Instance {
+        pub fn new(class: &Rc<Class>) -> Self {
+            Instance {
+                class: Rc::clone(class),
+                fields: HashMap::new(),
+            }
+        }
+
+        /// Looks a property up on the instance itself first, then among the
+        /// methods of its class, bound to the instance.
+        pub fn get(instance: &Rc<RefCell<Instance>>, name: &Token) -> Result<Object, Error> {
+            if let Some(value) = instance.borrow().fields.get(&*name.key()) {
+                return Ok(value.clone());
+            }
+
+            let class = Rc::clone(&instance.borrow().class);
+            match class.find_method(&name.key()) {
+                Some(method) => Ok(Object::Callable(method.bind(Object::Instance(Rc::clone(instance))))),
+                None => Err(Error::runtime(name, format!("Undefined property '{}'.", name.lexeme))),
+            }
+        }
+
+        pub fn set(&mut self, name: &Token, value: Object) {
+            self.fields.insert(name.key().into_owned(), value);
+        }
+
+        pub fn fields(&self) -> impl Iterator<Item = &Object> {
+            self.fields.values()
+        }
+
+        pub fn clear(&mut self) {
+            self.fields.clear();
+        }
+    }
+
+    impl fmt::Display for Instance {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "{} instance", self.class.name)
+        }
+    }
+}
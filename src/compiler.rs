@@ -0,0 +1,550 @@
+pub mod compiler {
+    use std::rc::Rc;
+
+    use crate::{expr::expr::{self, Expr, ExprId, LiteralValue}, object::object::Object, parser::parser::Error, stmt::stmt::{self, Stmt}, token::token::{Token, TokenType}, vm::vm::{Capture, Chunk, Op, Prototype}};
+
+    /// Compiles resolved statements into the prototype of a script, ready
+    /// for `Vm::interpret`.
+    pub fn compile(statements: &[Stmt]) -> Rc<Prototype> {
+        let mut compiler = Compiler { functions: vec![FunctionState::new("<script>", FunctionType::Script)] };
+        for statement in statements {
+            compiler.statement(statement);
+        }
+        compiler.emit(Op::Nil, None);
+        compiler.emit(Op::Return, None);
+        Rc::new(compiler.end_function())
+    }
+
+    #[derive(Clone, Copy, PartialEq)]
+    enum FunctionType {
+        Script,
+        Function,
+        Initializer,
+        Method,
+    }
+
+    struct Local {
+        name: String,
+        depth: usize,
+        captured: bool,
+    }
+
+    /// Jumps waiting for the end of a loop or for the start of its
+    /// increment.
+    struct Loop {
+        depth: usize,
+        breaks: Vec<usize>,
+        continues: Vec<usize>,
+    }
+
+    struct FunctionState {
+        name: String,
+        kind: FunctionType,
+        arity: usize,
+        chunk: Chunk,
+        locals: Vec<Local>,
+        captures: Vec<Capture>,
+        depth: usize,
+        loops: Vec<Loop>,
+    }
+
+    impl FunctionState {
+        /// Slot 0 holds the function being called, or the receiver of a
+        /// method, which is what `this` reads.
+        fn new(name: &str, kind: FunctionType) -> Self {
+            let receiver = match kind {
+                FunctionType::Method | FunctionType::Initializer => "this",
+                FunctionType::Function | FunctionType::Script => "",
+            };
+            FunctionState {
+                name: name.to_string(),
+                kind,
+                arity: 0,
+                chunk: Chunk::default(),
+                locals: vec![Local { name: receiver.to_string(), depth: 0, captured: false }],
+                captures: Vec::new(),
+                depth: 0,
+                loops: Vec::new(),
+            }
+        }
+    }
+
+    /// Where a variable lives, found from the innermost function outwards
+    /// the same way the resolver finds its scope.
+    enum Variable {
+        Local(u32),
+        Upvalue(u32),
+        Global,
+    }
+
+    struct Compiler {
+        functions: Vec<FunctionState>,
+    }
+
+    impl Compiler {
+        fn current(&mut self) -> &mut FunctionState {
+            self.functions.last_mut().expect("No function being compiled.")
+        }
+
+        fn statement(&mut self, statement: &Stmt) {
+            let _ = statement.accept(self);
+        }
+
+        fn expression(&mut self, expression: &Expr) {
+            let _ = expression.accept(self);
+        }
+
+        fn emit(&mut self, op: Op, site: Option<&Token>) -> usize {
+            let chunk = &mut self.current().chunk;
+            chunk.write(op, site);
+            chunk.code.len() - 1
+        }
+
+        fn constant(&mut self, value: Object) -> u32 {
+            let constants = &mut self.current().chunk.constants;
+            constants.push(value);
+            (constants.len() - 1) as u32
+        }
+
+        /// Points the jump at `from` to the next instruction.
+        fn patch_jump(&mut self, from: usize) {
+            let chunk = &mut self.current().chunk;
+            let offset = (chunk.code.len() - from - 1) as u32;
+            chunk.code[from] = match chunk.code[from] {
+                Op::Jump(_) => Op::Jump(offset),
+                Op::JumpIfFalse(_) => Op::JumpIfFalse(offset),
+                op => unreachable!("Cannot patch {:?}.", op),
+            };
+        }
+
+        fn emit_loop(&mut self, start: usize) {
+            let offset = (self.current().chunk.code.len() - start + 1) as u32;
+            self.emit(Op::Loop(offset), None);
+        }
+
+        fn begin_scope(&mut self) {
+            self.current().depth += 1;
+        }
+
+        fn end_scope(&mut self) {
+            let function = self.current();
+            function.depth -= 1;
+            let depth = function.depth;
+            while function.locals.last().is_some_and(|local| local.depth > depth) {
+                let local = function.locals.pop().expect("Checked above.");
+                let op = if local.captured { Op::CloseUpvalue } else { Op::Pop };
+                function.chunk.write(op, None);
+            }
+        }
+
+        /// Declares `name` in the current scope. Locals need their value on
+        /// the stack already; globals take it from the stack.
+        fn define(&mut self, name: &Token) {
+            if self.current().depth > 0 {
+                let depth = self.current().depth;
+                self.current().locals.push(Local { name: name.key().into_owned(), depth, captured: false });
+            } else {
+                self.emit(Op::DefineGlobal, Some(name));
+            }
+        }
+
+        fn resolve(&mut self, function: usize, name: &str) -> Variable {
+            if let Some(slot) = self.functions[function].locals.iter().rposition(|local| local.name == name) {
+                return Variable::Local(slot as u32);
+            }
+            if function == 0 {
+                return Variable::Global;
+            }
+
+            let capture = match self.resolve(function - 1, name) {
+                Variable::Local(slot) => {
+                    self.functions[function - 1].locals[slot as usize].captured = true;
+                    Capture { local: true, index: slot }
+                }
+                Variable::Upvalue(index) => Capture { local: false, index },
+                Variable::Global => return Variable::Global,
+            };
+
+            let captures = &mut self.functions[function].captures;
+            let index = captures
+                .iter()
+                .position(|existing| existing.local == capture.local && existing.index == capture.index)
+                .unwrap_or_else(|| {
+                    captures.push(capture);
+                    captures.len() - 1
+                });
+            Variable::Upvalue(index as u32)
+        }
+
+        fn get_variable(&mut self, name: &str, site: &Token) {
+            let op = match self.resolve(self.functions.len() - 1, name) {
+                Variable::Local(slot) => Op::GetLocal(slot),
+                Variable::Upvalue(index) => Op::GetUpvalue(index),
+                Variable::Global => Op::GetGlobal,
+            };
+            self.emit(op, Some(site));
+        }
+
+        fn set_variable(&mut self, name: &str, site: &Token) {
+            let op = match self.resolve(self.functions.len() - 1, name) {
+                Variable::Local(slot) => Op::SetLocal(slot),
+                Variable::Upvalue(index) => Op::SetUpvalue(index),
+                Variable::Global => Op::SetGlobal,
+            };
+            self.emit(op, Some(site));
+        }
+
+        fn end_function(&mut self) -> Prototype {
+            let function = self.functions.pop().expect("No function being compiled.");
+            Prototype {
+                name: function.name,
+                arity: function.arity,
+                chunk: function.chunk,
+                captures: function.captures,
+            }
+        }
+
+        /// Compiles a function body and leaves a closure over it on the
+        /// stack.
+        fn function(&mut self, name: &Token, params: &[Token], body: &[Stmt], kind: FunctionType) {
+            let mut function = FunctionState::new(&name.lexeme, kind);
+            function.arity = params.len();
+            function.depth = 1;
+            self.functions.push(function);
+
+            for param in params {
+                self.define(param);
+            }
+            for statement in body {
+                self.statement(statement);
+            }
+            self.emit_return(None);
+
+            let prototype = self.end_function();
+            let prototypes = &mut self.current().chunk.prototypes;
+            prototypes.push(Rc::new(prototype));
+            let index = (prototypes.len() - 1) as u32;
+            self.emit(Op::Closure(index), Some(name));
+        }
+
+        /// Initializers always return the instance, found in slot 0.
+        fn emit_return(&mut self, value: Option<&Expr>) {
+            match value {
+                Some(value) => self.expression(value),
+                None if self.current().kind == FunctionType::Initializer => {
+                    self.emit(Op::GetLocal(0), None);
+                }
+                None => {
+                    self.emit(Op::Nil, None);
+                }
+            }
+            self.emit(Op::Return, None);
+        }
+
+        /// Pops the locals of the scopes a `break` or `continue` jumps out
+        /// of, leaving them declared for the code after it.
+        fn leave_loop_scopes(&mut self) {
+            let function = self.current();
+            let depth = function.loops.last().expect("Loop control outside of a loop.").depth;
+            let ops: Vec<Op> = function
+                .locals
+                .iter()
+                .rev()
+                .take_while(|local| local.depth > depth)
+                .map(|local| if local.captured { Op::CloseUpvalue } else { Op::Pop })
+                .collect();
+            for op in ops {
+                function.chunk.write(op, None);
+            }
+        }
+    }
+
+    impl expr::Visitor<()> for Compiler {
+        fn visit_assign_expr(&mut self, _id: ExprId, name: &Token, value: &Expr) -> Result<(), Error> {
+            self.expression(value);
+            self.set_variable(&name.key(), name);
+            Ok(())
+        }
+
+        fn visit_binary_expr(&mut self, left: &Expr, operator: &Token, right: &Expr) -> Result<(), Error> {
+            self.expression(left);
+            self.expression(right);
+            let op = match operator.token_type {
+                TokenType::BangEqual => Op::NotEqual,
+                TokenType::EqualEqual => Op::Equal,
+                TokenType::Greater => Op::Greater,
+                TokenType::GreaterEqual => Op::GreaterEqual,
+                TokenType::Less => Op::Less,
+                TokenType::LessEqual => Op::LessEqual,
+                TokenType::Minus => Op::Subtract,
+                TokenType::Plus => Op::Add,
+                TokenType::Slash => Op::Divide,
+                TokenType::Star => Op::Multiply,
+                _ => unreachable!(),
+            };
+            self.emit(op, Some(operator));
+            Ok(())
+        }
+
+        fn visit_call_expr(&mut self, callee: &Expr, paren: &Token, arguments: &[Expr]) -> Result<(), Error> {
+            self.expression(callee);
+            for argument in arguments {
+                self.expression(argument);
+            }
+            self.emit(Op::Call(arguments.len() as u32), Some(paren));
+            Ok(())
+        }
+
+        fn visit_get_expr(&mut self, object: &Expr, name: &Token) -> Result<(), Error> {
+            self.expression(object);
+            self.emit(Op::GetProperty, Some(name));
+            Ok(())
+        }
+
+        fn visit_grouping_expr(&mut self, expression: &Expr) -> Result<(), Error> {
+            self.expression(expression);
+            Ok(())
+        }
+
+        fn visit_index_expr(&mut self, object: &Expr, bracket: &Token, index: &Expr) -> Result<(), Error> {
+            self.expression(object);
+            self.expression(index);
+            self.emit(Op::Index, Some(bracket));
+            Ok(())
+        }
+
+        fn visit_list_expr(&mut self, bracket: &Token, elements: &[Expr]) -> Result<(), Error> {
+            for element in elements {
+                self.expression(element);
+            }
+            self.emit(Op::List(elements.len() as u32), Some(bracket));
+            Ok(())
+        }
+
+        fn visit_literal_expr(&mut self, value: &LiteralValue) -> Result<(), Error> {
+            let op = match value {
+                LiteralValue::Boolean(true) => Op::True,
+                LiteralValue::Boolean(false) => Op::False,
+                LiteralValue::Null => Op::Nil,
+                LiteralValue::Number(n) => Op::Constant(self.constant(Object::Number(*n))),
+                LiteralValue::String(s) => Op::Constant(self.constant(Object::String(s.clone()))),
+            };
+            self.emit(op, None);
+            Ok(())
+        }
+
+        fn visit_map_expr(&mut self, brace: &Token, entries: &[(Expr, Expr)]) -> Result<(), Error> {
+            for (key, value) in entries {
+                self.expression(key);
+                self.expression(value);
+            }
+            self.emit(Op::Map(entries.len() as u32), Some(brace));
+            Ok(())
+        }
+
+        fn visit_logical_expr(&mut self, left: &Expr, operator: &Token, right: &Expr) -> Result<(), Error> {
+            self.expression(left);
+            if operator.token_type == TokenType::Or {
+                let otherwise = self.emit(Op::JumpIfFalse(0), None);
+                let end = self.emit(Op::Jump(0), None);
+                self.patch_jump(otherwise);
+                self.emit(Op::Pop, None);
+                self.expression(right);
+                self.patch_jump(end);
+            } else {
+                let end = self.emit(Op::JumpIfFalse(0), None);
+                self.emit(Op::Pop, None);
+                self.expression(right);
+                self.patch_jump(end);
+            }
+            Ok(())
+        }
+
+        fn visit_set_expr(&mut self, object: &Expr, name: &Token, value: &Expr) -> Result<(), Error> {
+            self.expression(object);
+            self.expression(value);
+            self.emit(Op::SetProperty, Some(name));
+            Ok(())
+        }
+
+        fn visit_set_index_expr(&mut self, object: &Expr, bracket: &Token, index: &Expr, value: &Expr) -> Result<(), Error> {
+            self.expression(object);
+            self.expression(index);
+            self.expression(value);
+            self.emit(Op::SetIndex, Some(bracket));
+            Ok(())
+        }
+
+        fn visit_super_expr(&mut self, _id: ExprId, keyword: &Token, method: &Token) -> Result<(), Error> {
+            self.get_variable("this", keyword);
+            self.get_variable("super", keyword);
+            self.emit(Op::GetSuper, Some(method));
+            Ok(())
+        }
+
+        fn visit_this_expr(&mut self, _id: ExprId, keyword: &Token) -> Result<(), Error> {
+            self.get_variable("this", keyword);
+            Ok(())
+        }
+
+        fn visit_unary_expr(&mut self, operator: &Token, right: &Expr) -> Result<(), Error> {
+            self.expression(right);
+            let op = match operator.token_type {
+                TokenType::Minus => Op::Negate,
+                TokenType::Bang => Op::Not,
+                _ => unreachable!(),
+            };
+            self.emit(op, Some(operator));
+            Ok(())
+        }
+
+        fn visit_variable_expr(&mut self, _id: ExprId, name: &Token) -> Result<(), Error> {
+            self.get_variable(&name.key(), name);
+            Ok(())
+        }
+    }
+
+    impl stmt::Visitor<()> for Compiler {
+        fn visit_block_stmt(&mut self, statements: &[Stmt]) -> Result<(), Error> {
+            self.begin_scope();
+            for statement in statements {
+                self.statement(statement);
+            }
+            self.end_scope();
+            Ok(())
+        }
+
+        fn visit_break_stmt(&mut self, _keyword: &Token) -> Result<(), Error> {
+            self.leave_loop_scopes();
+            let jump = self.emit(Op::Jump(0), None);
+            self.current().loops.last_mut().expect("Checked by the resolver.").breaks.push(jump);
+            Ok(())
+        }
+
+        fn visit_class_stmt(&mut self, name: &Token, superclass: &Option<Expr>, methods: &[Stmt]) -> Result<(), Error> {
+            // The class is declared first so its methods can refer to it.
+            self.emit(Op::Nil, Some(name));
+            self.define(name);
+
+            let mut site = name;
+            if let Some(superclass) = superclass {
+                if let Expr::Variable { name, .. } = superclass {
+                    site = name;
+                }
+                self.expression(superclass);
+                self.begin_scope();
+                let depth = self.current().depth;
+                self.current().locals.push(Local { name: "super".to_string(), depth, captured: false });
+            }
+
+            for method in methods {
+                if let Stmt::Function { name, params, body, .. } = method {
+                    let kind = if name.key() == "init" { FunctionType::Initializer } else { FunctionType::Method };
+                    self.function(name, params, body, kind);
+                }
+            }
+
+            let class_name = self.constant(Object::String(name.lexeme.as_str().into()));
+            let op = Op::Class { name: class_name, methods: methods.len() as u32, inherits: superclass.is_some() };
+            self.emit(op, Some(site));
+            self.set_variable(&name.key(), name);
+            self.emit(Op::Pop, None);
+            if superclass.is_some() {
+                self.end_scope();
+            }
+            Ok(())
+        }
+
+        fn visit_continue_stmt(&mut self, _keyword: &Token) -> Result<(), Error> {
+            self.leave_loop_scopes();
+            let jump = self.emit(Op::Jump(0), None);
+            self.current().loops.last_mut().expect("Checked by the resolver.").continues.push(jump);
+            Ok(())
+        }
+
+        fn visit_expression_stmt(&mut self, expression: &Expr) -> Result<(), Error> {
+            self.expression(expression);
+            self.emit(Op::Pop, None);
+            Ok(())
+        }
+
+        fn visit_function_stmt(&mut self, name: &Token, params: &Rc<[Token]>, body: &Rc<[Stmt]>) -> Result<(), Error> {
+            // A local function is declared before its body, so it can call
+            // itself; the closure then lands in the slot it was given.
+            if self.current().depth > 0 {
+                self.define(name);
+                self.function(name, params, body, FunctionType::Function);
+            } else {
+                self.function(name, params, body, FunctionType::Function);
+                self.define(name);
+            }
+            Ok(())
+        }
+
+        fn visit_if_stmt(&mut self, condition: &Expr, else_branch: &Option<Stmt>, then_branch: &Stmt) -> Result<(), Error> {
+            self.expression(condition);
+            let otherwise = self.emit(Op::JumpIfFalse(0), None);
+            self.emit(Op::Pop, None);
+            self.statement(then_branch);
+            let end = self.emit(Op::Jump(0), None);
+
+            self.patch_jump(otherwise);
+            self.emit(Op::Pop, None);
+            if let Some(else_branch) = else_branch {
+                self.statement(else_branch);
+            }
+            self.patch_jump(end);
+            Ok(())
+        }
+
+        fn visit_print_stmt(&mut self, expression: &Expr) -> Result<(), Error> {
+            self.expression(expression);
+            self.emit(Op::Print, None);
+            Ok(())
+        }
+
+        fn visit_return_stmt(&mut self, _keyword: &Token, value: &Option<Expr>) -> Result<(), Error> {
+            self.emit_return(value.as_ref());
+            Ok(())
+        }
+
+        fn visit_var_stmt(&mut self, name: &Token, initializer: &Option<Expr>) -> Result<(), Error> {
+            match initializer {
+                Some(initializer) => self.expression(initializer),
+                None => {
+                    self.emit(Op::Nil, None);
+                }
+            }
+            self.define(name);
+            Ok(())
+        }
+
+        fn visit_while_stmt(&mut self, condition: &Expr, body: &Stmt, increment: &Option<Expr>) -> Result<(), Error> {
+            let start = self.current().chunk.code.len();
+            self.expression(condition);
+            let exit = self.emit(Op::JumpIfFalse(0), None);
+            self.emit(Op::Pop, None);
+
+            let depth = self.current().depth;
+            self.current().loops.push(Loop { depth, breaks: Vec::new(), continues: Vec::new() });
+            self.statement(body);
+            let the_loop = self.current().loops.pop().expect("Pushed above.");
+
+            for jump in the_loop.continues {
+                self.patch_jump(jump);
+            }
+            if let Some(increment) = increment {
+                self.expression(increment);
+                self.emit(Op::Pop, None);
+            }
+            self.emit_loop(start);
+
+            self.patch_jump(exit);
+            self.emit(Op::Pop, None);
+            for jump in the_loop.breaks {
+                self.patch_jump(jump);
+            }
+            Ok(())
+        }
+    }
+}
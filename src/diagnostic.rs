@@ -0,0 +1,185 @@
+pub mod diagnostic {
+    use std::{env, fmt::Write, io::{self, IsTerminal}};
+
+    use crate::{span::span::Span, unicode::unicode};
+
+    /// An error found in a script, located by its span.
+    ///
+    /// Every error has a code, grouped by the phase that finds it:
+    ///
+    /// | Code | Error |
+    /// |------|-------|
+    /// | E001 | Unexpected character |
+    /// | E002 | Unterminated string |
+    /// | E101 | Missing token |
+    /// | E102 | Expression expected |
+    /// | E103 | Invalid assignment target |
+    /// | E104 | Too many parameters or arguments |
+    /// | E201 | Variable declared twice in one scope |
+    /// | E202 | Variable read in its own initializer |
+    /// | E203 | Return outside of a function |
+    /// | E204 | Value returned from an initializer |
+    /// | E205 | `this` outside of a class |
+    /// | E206 | `super` outside of a class |
+    /// | E207 | `super` in a class without a superclass |
+    /// | E208 | Class inheriting from itself |
+    /// | E209 | `break` outside of a loop |
+    /// | E210 | `continue` outside of a loop |
+    /// | E301 | Runtime error |
+    #[derive(Clone, Debug, PartialEq)]
+    pub struct Diagnostic {
+        pub code: &'static str,
+        pub message: String,
+        pub span: Span,
+        pub label: Option<String>,
+        pub notes: Vec<String>,
+        pub help: Option<String>,
+        pub backtrace: Vec<String>,
+    }
+
+    impl Diagnostic {
+        pub fn error(code: &'static str, message: impl Into<String>, span: Span) -> Self {
+            Diagnostic {
+                code,
+                message: message.into(),
+                span,
+                label: None,
+                notes: Vec::new(),
+                help: None,
+                backtrace: Vec::new(),
+            }
+        }
+
+        /// A short text shown right after the underline.
+        pub fn with_label(mut self, label: impl Into<String>) -> Self {
+            self.label = Some(label.into());
+            self
+        }
+
+        pub fn with_note(mut self, note: impl Into<String>) -> Self {
+            self.notes.push(note.into());
+            self
+        }
+
+        pub fn with_help(mut self, help: impl Into<String>) -> Self {
+            self.help = Some(help.into());
+            self
+        }
+
+        /// Lines listing where the error happened, innermost first, shown
+        /// after everything else.
+        pub fn with_backtrace(mut self, backtrace: Vec<String>) -> Self {
+            self.backtrace = backtrace;
+            self
+        }
+
+        /// Renders the diagnostic with the line of `source` it points at,
+        /// underlined:
+        ///
+        /// ```text
+        /// error[E101]: Expect ';' (🔚) after value.
+        ///  --> script.emojica:1:11
+        ///   |
+        /// 1 | print 🍕 2;
+        ///   |          ^ found '2'
+        /// ```
+        pub fn render(&self, name: &str, source: &str, style: Style) -> String {
+            let line_start = source[..self.span.start.min(source.len())]
+                .rfind('\n')
+                .map(|newline| newline + 1)
+                .unwrap_or(0);
+            let line_end = source[line_start..]
+                .find('\n')
+                .map(|newline| line_start + newline)
+                .unwrap_or(source.len());
+            let line = &source[line_start..line_end];
+
+            let start = self.span.start.clamp(line_start, line_end);
+            let end = self.span.end.clamp(start, line_end);
+            let indent = display_width(&source[line_start..start]);
+            let underline = display_width(&source[start..end]).max(1);
+
+            let number = self.span.line.to_string();
+            let gutter = " ".repeat(number.len());
+            let bar = style.paint(Style::GUTTER, "|");
+
+            let mut output = String::new();
+            let _ = writeln!(
+                output,
+                "{}{}",
+                style.paint(Style::ERROR, &format!("error[{}]", self.code)),
+                style.paint(Style::BOLD, &format!(": {}", self.message))
+            );
+            let _ = writeln!(
+                output,
+                "{}{} {}:{}:{}",
+                gutter,
+                style.paint(Style::GUTTER, "-->"),
+                name,
+                self.span.line,
+                self.span.column
+            );
+            let _ = writeln!(output, "{} {}", gutter, bar);
+            let _ = writeln!(output, "{} {} {}", style.paint(Style::GUTTER, &number), bar, line.replace('\t', TAB));
+
+            let mut marker = "^".repeat(underline);
+            if let Some(label) = &self.label {
+                marker.push(' ');
+                marker.push_str(label);
+            }
+            let _ = writeln!(output, "{} {} {}{}", gutter, bar, " ".repeat(indent), style.paint(Style::ERROR, &marker));
+
+            for note in &self.notes {
+                let _ = writeln!(output, "{} {} note: {}", gutter, style.paint(Style::GUTTER, "="), note);
+            }
+            if let Some(help) = &self.help {
+                let _ = writeln!(output, "{} {} help: {}", gutter, style.paint(Style::GUTTER, "="), help);
+            }
+            for line in &self.backtrace {
+                let _ = writeln!(output, "{}   {}", gutter, line);
+            }
+            output.push('\n');
+
+            output
+        }
+    }
+
+    const TAB: &str = "    ";
+
+    fn display_width(text: &str) -> usize {
+        unicode::width(&text.replace('\t', TAB))
+    }
+
+    /// Whether rendered diagnostics are coloured.
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub enum Style {
+        Plain,
+        Colour,
+    }
+
+    impl Style {
+        const ERROR: &'static str = "1;31";
+        const GUTTER: &'static str = "1;34";
+        const BOLD: &'static str = "1";
+
+        /// Colour when stderr is a terminal. `NO_COLOR` turns colour off and
+        /// `CLICOLOR_FORCE` turns it on regardless.
+        pub fn for_stderr() -> Self {
+            let set = |name: &str| env::var_os(name).is_some_and(|value| !value.is_empty() && value != "0");
+            if set("NO_COLOR") {
+                Style::Plain
+            } else if set("CLICOLOR_FORCE") || io::stderr().is_terminal() {
+                Style::Colour
+            } else {
+                Style::Plain
+            }
+        }
+
+        fn paint(&self, colour: &str, text: &str) -> String {
+            match self {
+                Style::Plain => text.to_string(),
+                Style::Colour => format!("\x1b[{}m{}\x1b[0m", colour, text),
+            }
+        }
+    }
+}
@@ -4,16 +4,26 @@ pub mod environment {
     use crate::{object::object::Object, parser::parser::Error, token::token::Token};
 
 
+    /// A scope. Locals live in slots numbered by the resolver in the order
+    /// they are declared; globals are looked up by name.
     #[derive(Debug)]
     pub struct Environment {
         pub enclosing: Option<Rc<RefCell<Environment>>>,
+        slots: Vec<Object>,
         values: HashMap<String, Object>
     }
 
+    impl Default for Environment {
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+
     impl Environment {
         pub fn new() -> Self {
             Environment {
                 enclosing: None,
+                slots: Vec::new(),
                 values: HashMap::new(),
             }
         }
@@ -21,6 +31,7 @@ pub mod environment {
         pub fn from(enclosing: &Rc<RefCell<Environment>>) -> Self {
             Environment {
                 enclosing: Some(Rc::clone(enclosing)),
+                slots: Vec::new(),
                 values: HashMap::new(),
             }
         }
@@ -29,12 +40,22 @@ pub mod environment {
             self.values.insert(name, value);
         }
 
+        /// Every value held in this scope, locals and globals alike.
+        pub fn objects(&self) -> impl Iterator<Item = &Object> {
+            self.slots.iter().chain(self.values.values())
+        }
+
+        /// Declares the next local slot of this scope.
+        pub fn push(&mut self, value: Object) {
+            self.slots.push(value);
+        }
+
         fn ancestor(&self, distance: usize) -> Rc<RefCell<Environment>> {
             // Get first ancestor
             let parent = self
                 .enclosing
                 .clone()
-                .expect(&format!("No enclosing environment at {}", 1));
+                .unwrap_or_else(|| panic!("No enclosing environment at {}", 1));
             let mut environment = Rc::clone(&parent);
 
             // Get next ancestors
@@ -43,71 +64,59 @@ pub mod environment {
                     .borrow()
                     .enclosing
                     .clone()
-                    .expect(&format!("No enclosing environment at {}", i));
+                    .unwrap_or_else(|| panic!("No enclosing environment at {}", i));
                 environment = Rc::clone(&parent);
             }
             environment
         }
 
-        pub fn get_at(&self, distance: usize, name: &str) -> Result<Object, Error> {
+        pub fn get_at(&self, distance: usize, slot: usize) -> Result<Object, Error> {
             if distance > 0 {
-                Ok(self
-                    .ancestor(distance)
-                    .borrow()
-                    .values
-                    .get(name)
-                    .expect(&format!("Undefined variable '{}'", name))
-                    .clone())
+                Ok(self.ancestor(distance).borrow().slot(slot).clone())
             } else {
-                Ok(self
-                    .values
-                    .get(name)
-                    .expect(&format!("Undefined variable '{}'", name))
-                    .clone())
+                Ok(self.slot(slot).clone())
             }
         }
 
-        pub fn assign_at(&mut self, distance: usize, name: &Token, value: Object) -> Result<(), Error> {
+        pub fn assign_at(&mut self, distance: usize, slot: usize, value: Object) -> Result<(), Error> {
             if distance > 0 {
-                self.ancestor(distance)
-                    .borrow_mut()
-                    .values
-                    .insert(name.lexeme.clone(), value);
+                *self.ancestor(distance).borrow_mut().slot_mut(slot) = value;
             } else {
-                self.values.insert(name.lexeme.clone(), value);
+                *self.slot_mut(slot) = value;
             }
             Ok(())
         }
 
+        fn slot(&self, slot: usize) -> &Object {
+            self.slots.get(slot).unwrap_or_else(|| panic!("Undefined local slot {}", slot))
+        }
+
+        fn slot_mut(&mut self, slot: usize) -> &mut Object {
+            self.slots.get_mut(slot).unwrap_or_else(|| panic!("Undefined local slot {}", slot))
+        }
+
         pub fn get(&self, name: &Token) -> Result<Object, Error> {
-            let key = &*name.lexeme;
-            if let Some(value) = self.values.get(key) {
+            if let Some(value) = self.values.get(&*name.key()) {
                 Ok((*value).clone())
             } else {
                 if let Some(ref enclosing) = self.enclosing {
                     enclosing.borrow().get(name)
                 } else {
-                    Err(Error::Runtime {
-                        token: name.clone(),
-                        message: format!("Undefined variable '{}'.", key),
-                    })
+                    Err(Error::runtime(name, format!("Undefined variable '{}'.", name.lexeme)))
                 }
             }
         }
 
         pub fn assign(&mut self, name: &Token, value: Object) -> Result<(), Error> {
-            let key = &*name.lexeme;
-            if self.values.contains_key(key) {
-                self.values.insert(name.lexeme.clone(), value);
+            let key = name.key();
+            if self.values.contains_key(&*key) {
+                self.values.insert(key.into_owned(), value);
                 Ok(())
             } else {
                 if let Some(ref enclosing) = self.enclosing {
                     enclosing.borrow_mut().assign(name, value)
                 } else {
-                    Err(Error::Runtime {
-                        token: name.clone(),
-                        message: format!("Undefined variable '{}'", key),
-                    })
+                    Err(Error::runtime(name, format!("Undefined variable '{}'", name.lexeme)))
                 }
             }
         }
@@ -1,39 +1,106 @@
 pub mod expr {
-    use crate::{parser::parser::Error, token::token::Token};
-    
+    use std::{rc::Rc, sync::atomic::{AtomicUsize, Ordering}};
+
+    use crate::{parser::parser::Error, span::span::Span, token::token::Token};
+
+    /// Identifies an expression that refers to a variable, so the resolver
+    /// can record its scope depth for that exact use.
+    pub type ExprId = usize;
+
+    static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+
+    pub fn next_id() -> ExprId {
+        NEXT_ID.fetch_add(1, Ordering::Relaxed)
+    }
+
     #[derive(Clone, Debug)]
     pub enum Expr {
         Assign {
+            id: ExprId,
             name: Token,
-            value: Box<Expr>
+            value: Box<Expr>,
+            span: Span,
         },
         Binary {
             left: Box<Expr>,
             operator: Token,
             right: Box<Expr>,
+            span: Span,
         },
         Call {
             callee: Box<Expr>,
             paren: Token,
-            arguments: Vec<Expr>
+            arguments: Vec<Expr>,
+            span: Span,
+        },
+        Get {
+            object: Box<Expr>,
+            name: Token,
+            span: Span,
         },
         Grouping {
             expression: Box<Expr>,
+            span: Span,
+        },
+        Index {
+            object: Box<Expr>,
+            bracket: Token,
+            index: Box<Expr>,
+            span: Span,
+        },
+        List {
+            bracket: Token,
+            elements: Vec<Expr>,
+            span: Span,
         },
         Literal {
             value: LiteralValue,
+            span: Span,
+        },
+        Map {
+            brace: Token,
+            entries: Vec<(Expr, Expr)>,
+            span: Span,
         },
         Logical {
             left: Box<Expr>,
             operator: Token,
-            right: Box<Expr>
+            right: Box<Expr>,
+            span: Span,
+        },
+        Set {
+            object: Box<Expr>,
+            name: Token,
+            value: Box<Expr>,
+            span: Span,
+        },
+        SetIndex {
+            object: Box<Expr>,
+            bracket: Token,
+            index: Box<Expr>,
+            value: Box<Expr>,
+            span: Span,
+        },
+        Super {
+            id: ExprId,
+            keyword: Token,
+            method: Token,
+            span: Span,
+        },
+        This {
+            id: ExprId,
+            keyword: Token,
+            span: Span,
         },
         Unary {
             operator: Token,
-            right: Box<Expr>
+            right: Box<Expr>,
+            span: Span,
         },
         Variable {
+            id: ExprId,
             name: Token,
+            span: Span,
         }
     }
 
@@ -42,37 +109,76 @@ pub mod expr {
         Boolean(bool),
         Null,
         Number(f64),
-        String(String),
+        String(Rc<str>),
     }
 
     pub trait Visitor<R> {
-        fn visit_assign_expr(&mut self, name: &Token, value: &Expr) -> Result<R, Error>;
+        fn visit_assign_expr(&mut self, id: ExprId, name: &Token, value: &Expr) -> Result<R, Error>;
         fn visit_binary_expr(&mut self, left: &Expr, operator: &Token, right: &Expr) -> Result<R, Error>;
-        fn visit_call_expr(&mut self, callee: &Expr, paren: &Token, arguments: &Vec<Expr>) -> Result<R, Error>;
+        fn visit_call_expr(&mut self, callee: &Expr, paren: &Token, arguments: &[Expr]) -> Result<R, Error>;
+        fn visit_get_expr(&mut self, object: &Expr, name: &Token) -> Result<R, Error>;
         fn visit_grouping_expr(&mut self, expression: &Expr) -> Result<R, Error>;
+        fn visit_index_expr(&mut self, object: &Expr, bracket: &Token, index: &Expr) -> Result<R, Error>;
+        fn visit_list_expr(&mut self, bracket: &Token, elements: &[Expr]) -> Result<R, Error>;
         fn visit_literal_expr(&mut self, value: &LiteralValue) -> Result<R, Error>;
+        fn visit_map_expr(&mut self, brace: &Token, entries: &[(Expr, Expr)]) -> Result<R, Error>;
         fn visit_logical_expr(&mut self, left: &Expr, operator: &Token, right: &Expr) -> Result<R, Error>;
+        fn visit_set_expr(&mut self, object: &Expr, name: &Token, value: &Expr) -> Result<R, Error>;
+        fn visit_set_index_expr(&mut self, object: &Expr, bracket: &Token, index: &Expr, value: &Expr) -> Result<R, Error>;
+        fn visit_super_expr(&mut self, id: ExprId, keyword: &Token, method: &Token) -> Result<R, Error>;
+        fn visit_this_expr(&mut self, id: ExprId, keyword: &Token) -> Result<R, Error>;
         fn visit_unary_expr(&mut self, operator: &Token, right: &Expr) -> Result<R, Error>;
-        fn visit_variable_expr(&mut self, name: &Token) -> Result<R, Error>;
+        fn visit_variable_expr(&mut self, id: ExprId, name: &Token) -> Result<R, Error>;
     }
 
     impl Expr {
+        pub fn span(&self) -> Span {
+            match self {
+                Expr::Assign { span, .. }
+                | Expr::Binary { span, .. }
+                | Expr::Call { span, .. }
+                | Expr::Get { span, .. }
+                | Expr::Grouping { span, .. }
+                | Expr::Index { span, .. }
+                | Expr::List { span, .. }
+                | Expr::Literal { span, .. }
+                | Expr::Map { span, .. }
+                | Expr::Logical { span, .. }
+                | Expr::Set { span, .. }
+                | Expr::SetIndex { span, .. }
+                | Expr::Super { span, .. }
+                | Expr::This { span, .. }
+                | Expr::Unary { span, .. }
+                | Expr::Variable { span, .. } => *span,
+            }
+        }
+
         pub fn accept<R>(&self, visitor: &mut dyn Visitor<R>) -> Result<R, Error> {
             match self {
-                Expr::Assign { name, value } => visitor.visit_assign_expr(name, value),
-                Expr::Binary { left, operator, right } => { 
+                Expr::Assign { id, name, value, .. } => visitor.visit_assign_expr(*id, name, value),
+                Expr::Binary { left, operator, right, .. } => { 
                     visitor.visit_binary_expr(left, operator, right)
                 },
-                Expr::Call { callee, paren, arguments } => {
+                Expr::Call { callee, paren, arguments, .. } => {
                     visitor.visit_call_expr(callee, paren, arguments)
                 },
-                Expr::Grouping { expression } => visitor.visit_grouping_expr(expression),
-                Expr::Literal { value } => visitor.visit_literal_expr(value),
-                Expr::Logical { left, operator, right } => {
+                Expr::Get { object, name, .. } => visitor.visit_get_expr(object, name),
+                Expr::Grouping { expression, .. } => visitor.visit_grouping_expr(expression),
+                Expr::Index { object, bracket, index, .. } => visitor.visit_index_expr(object, bracket, index),
+                Expr::List { bracket, elements, .. } => visitor.visit_list_expr(bracket, elements),
+                Expr::Literal { value, .. } => visitor.visit_literal_expr(value),
+                Expr::Map { brace, entries, .. } => visitor.visit_map_expr(brace, entries),
+                Expr::Logical { left, operator, right, .. } => {
                     visitor.visit_logical_expr(left, operator, right)
                 },
-                Expr::Unary { operator, right } => visitor.visit_unary_expr(operator, right),
-                Expr::Variable { name } => visitor.visit_variable_expr(name),
+                Expr::Set { object, name, value, .. } => visitor.visit_set_expr(object, name, value),
+                Expr::SetIndex { object, bracket, index, value, .. } => {
+                    visitor.visit_set_index_expr(object, bracket, index, value)
+                },
+                Expr::Super { id, keyword, method, .. } => visitor.visit_super_expr(*id, keyword, method),
+                Expr::This { id, keyword, .. } => visitor.visit_this_expr(*id, keyword),
+                Expr::Unary { operator, right, .. } => visitor.visit_unary_expr(operator, right),
+                Expr::Variable { id, name, .. } => visitor.visit_variable_expr(*id, name),
             }
         }
     }
@@ -0,0 +1,99 @@
+pub mod formatter {
+    use crate::{keywords::keywords, scanner::scanner::word_len, unicode::unicode};
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub enum Spelling {
+        Ascii,
+        Emoji,
+    }
+
+    /// Respells every keyword and operator of `source` in the given
+    /// spelling. Everything else, including strings, comments, numbers and
+    /// whitespace, is copied as is.
+    pub fn format(source: &str, spelling: Spelling) -> String {
+        let mut output = String::with_capacity(source.len());
+        let mut current = 0;
+
+        while current < source.len() {
+            let rest = &source[current..];
+            let length = lexeme_len(rest);
+            let text = &rest[..length];
+            let following = source[current + length..].chars().next();
+
+            if let Some(keyword) = keywords::lookup(text) {
+                match spelling {
+                    Spelling::Ascii => {
+                        if output.chars().next_back().is_some_and(is_word_character) {
+                            output.push(' ');
+                        }
+                        output.push_str(keyword.word);
+                        if following.is_some_and(is_word_character) {
+                            output.push(' ');
+                        }
+                    }
+                    Spelling::Emoji => output.push_str(keyword.emoji),
+                }
+            } else if let Some(operator) = keywords::lookup_operator(text) {
+                match spelling {
+                    Spelling::Ascii => {
+                        // Keep `⬅️⬅️` from turning into `==`, or `➗➗` into a comment.
+                        if output.ends_with(['!', '=', '<', '>', '/']) && operator.symbol.starts_with(['=', '/']) {
+                            output.push(' ');
+                        }
+                        // Nor `1🔸5` into the number `1.5`.
+                        if operator.symbol == "."
+                            && output.ends_with(|c: char| c.is_ascii_digit())
+                            && following.is_some_and(|c| c.is_ascii_digit())
+                        {
+                            output.push(' ');
+                        }
+                        output.push_str(operator.symbol);
+                    }
+                    Spelling::Emoji => output.push_str(operator.emoji),
+                }
+            } else {
+                output.push_str(text);
+            }
+
+            current += length;
+        }
+
+        output
+    }
+
+    /// Byte length of the next unit of `text` that is respelled or copied
+    /// as a whole.
+    fn lexeme_len(text: &str) -> usize {
+        let character = text.chars().next().expect("Lexeme of empty text.");
+
+        if character == '"' {
+            text[1..].find('"').map(|end| end + 2).unwrap_or(text.len())
+        } else if text.starts_with("//") {
+            text.find('\n').unwrap_or(text.len())
+        } else if character.is_ascii_digit() {
+            let integer = text.find(|c: char| !c.is_ascii_digit()).unwrap_or(text.len());
+            let fraction = text[integer..].strip_prefix('.').map(|fraction| {
+                fraction.find(|c: char| !c.is_ascii_digit()).unwrap_or(fraction.len())
+            });
+            match fraction {
+                Some(digits) if digits > 0 => integer + 1 + digits,
+                _ => integer,
+            }
+        } else if keywords::emoji_operator_len(text) > 0 {
+            keywords::emoji_operator_len(text)
+        } else if word_len(text) > 0 {
+            word_len(text)
+        } else if ["!=", "==", "<=", ">="].iter().any(|symbol| text.starts_with(symbol)) {
+            2
+        } else {
+            character.len_utf8()
+        }
+    }
+
+    fn is_word_character(character: char) -> bool {
+        character.is_alphanumeric()
+            || character == '_'
+            || unicode::is_emoji(character)
+            || unicode::is_emoji_component(character)
+    }
+}
@@ -2,52 +2,110 @@ pub mod function {
     use core::fmt;
     use std::{cell::RefCell, rc::Rc};
 
-    use crate::{environment::environment::Environment, interpreter::interpreter::Interpreter, object::object::Object, parser::parser::Error, stmt::stmt::Stmt, token::token::Token};
+    use crate::{class::class::Instance, environment::environment::Environment, gc::gc, interpreter::interpreter::{Flow, Interpreter, Locals}, object::object::Object, parser::parser::Error, stmt::stmt::Stmt, token::token::Token, vm::vm::Closure};
 
+    /// The body of a native function. Natives can call back into whichever
+    /// engine runs them and report errors with `Runtime::error`.
+    pub type NativeFn = Rc<dyn Fn(&mut dyn Runtime, &[Object]) -> Result<Object, Error>>;
 
-    #[derive(Clone, Debug)]
+    /// What natives need from the engine running them: the tree-walking
+    /// `Interpreter` or the bytecode `Vm`.
+    pub trait Runtime {
+        /// Calls any callable value, checking the number of arguments first.
+        fn call(&mut self, callee: &Object, arguments: &[Object], paren: &Token) -> Result<Object, Error>;
+
+        /// The closing parenthesis of the native call being run.
+        fn call_site(&self) -> Token;
+    }
+
+    impl dyn Runtime + '_ {
+        /// A runtime error reported at the native call being run.
+        pub fn error(&self, message: impl Into<String>) -> Error {
+            Error::runtime(&self.call_site(), message)
+        }
+    }
+
+    /// How many arguments a function accepts.
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub enum Arity {
+        Exact(usize),
+        AtLeast(usize),
+    }
+
+    impl Arity {
+        pub fn accepts(&self, count: usize) -> bool {
+            match self {
+                Arity::Exact(n) => count == *n,
+                Arity::AtLeast(n) => count >= *n,
+            }
+        }
+    }
+
+    impl fmt::Display for Arity {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            let (n, bound) = match self {
+                Arity::Exact(n) => (n, ""),
+                Arity::AtLeast(n) => (n, "at least "),
+            };
+            write!(f, "{}{} argument{}", bound, n, if *n == 1 { "" } else { "s" })
+        }
+    }
+
+    #[derive(Clone)]
     pub enum Function {
         Native {
-            arity: usize,
-            body: Box<fn(&Vec<Object>) -> Object>,
+            name: String,
+            arity: Arity,
+            body: NativeFn,
         },
 
         User {
             name: Token,
-            params: Vec<Token>,
-            body: Vec<Stmt>,
+            params: Rc<[Token]>,
+            body: Rc<[Stmt]>,
             closure: Rc<RefCell<Environment>>,
+            locals: Locals,
             is_initializer: bool,
         },
+
+        /// A function compiled for the `Vm`. Methods are bound by giving
+        /// them a receiver, which the call puts in slot 0.
+        Closure {
+            closure: Rc<Closure>,
+            receiver: Option<Rc<RefCell<Instance>>>,
+        },
     }
 
     impl Function {
         pub fn call(
             &self,
             interpreter: &mut Interpreter,
-            arguments: &Vec<Object>,
+            arguments: &[Object],
         ) -> Result<Object, Error> {
             match self {
-                Function::Native { body, .. } => Ok(body(arguments)),
+                Function::Native { body, .. } => body(interpreter, arguments),
+                Function::Closure { .. } => unreachable!("Compiled functions only run on the VM."),
                 Function::User {
                     params,
                     body,
                     closure,
+                    locals,
                     is_initializer,
                     ..
                 } => {
-                    let environment = Rc::new(RefCell::new(Environment::from(closure)));
-                    for (param, argument) in params.iter().zip(arguments.iter()) {
-                        environment
-                            .borrow_mut()
-                            .define(param.lexeme.clone(), argument.clone());
+                    let environment = gc::track(RefCell::new(Environment::from(closure)));
+                    for argument in arguments.iter().take(params.len()) {
+                        environment.borrow_mut().push(argument.clone());
                     }
-                    match interpreter.execute_block(body, environment) {
-                        Err(Error::Return { value }) => {
+                    let caller = interpreter.replace_locals(Rc::clone(locals));
+                    let result = interpreter.execute_block(body, environment);
+                    interpreter.replace_locals(caller);
+                    match result {
+                        Ok(Flow::Return(value)) => {
                             if *is_initializer {
                                 Ok(closure
                                     .borrow()
-                                    .get_at(0, "this")
+                                    .get_at(0, 0)
                                     .expect("Initializer should return 'this'."))
                             } else {
                                 Ok(value)
@@ -58,7 +116,7 @@ pub mod function {
                             if *is_initializer {
                                 Ok(closure
                                     .borrow()
-                                    .get_at(0, "this")
+                                    .get_at(0, 0)
                                     .expect("Initializer should return 'this'."))
                             } else {
                                 Ok(Object::Null)
@@ -72,32 +130,69 @@ pub mod function {
         pub fn bind(&self, instance: Object) -> Self {
             match self {
                 Function::Native { .. } => unreachable!(),
+                Function::Closure { closure, .. } => match instance {
+                    Object::Instance(instance) => Function::Closure {
+                        closure: Rc::clone(closure),
+                        receiver: Some(instance),
+                    },
+                    _ => unreachable!(),
+                },
                 Function::User {
                     name,
                     params,
                     body,
                     closure,
+                    locals,
                     is_initializer,
                 } => {
-                    let environment = Rc::new(RefCell::new(Environment::from(closure)));
-                    environment
-                        .borrow_mut()
-                        .define("this".to_string(), instance);
+                    let environment = gc::track(RefCell::new(Environment::from(closure)));
+                    environment.borrow_mut().push(instance);
                     Function::User {
                         name: name.clone(),
-                        params: params.clone(),
-                        body: body.clone(),
+                        params: Rc::clone(params),
+                        body: Rc::clone(body),
                         closure: environment,
+                        locals: Rc::clone(locals),
                         is_initializer: *is_initializer,
                     }
                 }
             }
         }
 
-        pub fn arity(&self) -> usize {
+        pub fn name(&self) -> &str {
+            match self {
+                Function::Native { name, .. } => name,
+                Function::User { name, .. } => &name.lexeme,
+                Function::Closure { closure, .. } => &closure.prototype.name,
+            }
+        }
+
+        pub fn arity(&self) -> Arity {
             match self {
                 Function::Native { arity, .. } => *arity,
-                Function::User { params, .. } => params.len(),
+                Function::User { params, .. } => Arity::Exact(params.len()),
+                Function::Closure { closure, .. } => Arity::Exact(closure.prototype.arity),
+            }
+        }
+    }
+
+    impl fmt::Debug for Function {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                Function::Native { name, arity, .. } => f
+                    .debug_struct("Native")
+                    .field("name", name)
+                    .field("arity", arity)
+                    .finish_non_exhaustive(),
+                Function::User { name, params, .. } => f
+                    .debug_struct("User")
+                    .field("name", name)
+                    .field("params", params)
+                    .finish_non_exhaustive(),
+                Function::Closure { closure, .. } => f
+                    .debug_struct("Closure")
+                    .field("name", &closure.prototype.name)
+                    .finish_non_exhaustive(),
             }
         }
     }
@@ -105,8 +200,9 @@ pub mod function {
     impl fmt::Display for Function {
         fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
             match self {
-                Function::Native { .. } => write!(f, "<native func>"),
+                Function::Native { name, .. } => write!(f, "<native fn {}>", name),
                 Function::User { name, .. } => write!(f, "<fn {}>", name.lexeme),
+                Function::Closure { closure, .. } => write!(f, "<fn {}>", closure.prototype.name),
             }
         }
     }
@@ -0,0 +1,301 @@
+pub mod gc {
+    use std::{cell::RefCell, collections::HashMap, mem, rc::{Rc, Weak}};
+
+    use crate::{class::class::{Class, Instance}, environment::environment::Environment, function::function::Function, map::map::Map, object::object::Object, vm::vm::{Closure, Upvalue}};
+
+    /// Allocations between automatic collections while the heap is small.
+    const MIN_THRESHOLD: usize = 10_000;
+
+    thread_local! {
+        static HEAP: RefCell<Heap> = RefCell::new(Heap {
+            nodes: Vec::new(),
+            threshold: MIN_THRESHOLD,
+            stats: Stats::default(),
+        });
+    }
+
+    /// What the collector has done on this thread so far.
+    #[derive(Clone, Copy, Debug, Default)]
+    pub struct Stats {
+        /// Heap objects alive right now.
+        pub live: usize,
+        pub collections: usize,
+        /// Objects freed by breaking the cycles they were stuck in.
+        pub freed: usize,
+    }
+
+    /// Every heap object allocated since the last collection, and every one
+    /// that survived it.
+    struct Heap {
+        nodes: Vec<Node>,
+        threshold: usize,
+        stats: Stats,
+    }
+
+    /// A heap object the collector can look into.
+    pub trait Traced {
+        fn node(this: &Rc<Self>) -> Node;
+    }
+
+    macro_rules! nodes {
+        ( $( $kind:ident($type:ty) ),* ) => {
+            /// A weak handle on a heap object, so that tracking an object
+            /// does not keep it alive.
+            pub enum Node {
+                $( $kind(Weak<$type>), )*
+            }
+
+            /// A heap object held alive for the length of a collection.
+            enum Live {
+                $( $kind(Rc<$type>), )*
+            }
+
+            $(
+                impl Traced for $type {
+                    fn node(this: &Rc<Self>) -> Node {
+                        Node::$kind(Rc::downgrade(this))
+                    }
+                }
+            )*
+
+            impl Node {
+                fn upgrade(&self) -> Option<Live> {
+                    match self {
+                        $( Node::$kind(weak) => weak.upgrade().map(Live::$kind), )*
+                    }
+                }
+
+                fn is_alive(&self) -> bool {
+                    match self {
+                        $( Node::$kind(weak) => weak.strong_count() > 0, )*
+                    }
+                }
+            }
+
+            impl Live {
+                fn address(&self) -> *const () {
+                    match self {
+                        $( Live::$kind(object) => Rc::as_ptr(object) as *const (), )*
+                    }
+                }
+
+                fn strong_count(&self) -> usize {
+                    match self {
+                        $( Live::$kind(object) => Rc::strong_count(object), )*
+                    }
+                }
+
+                fn downgrade(&self) -> Node {
+                    match self {
+                        $( Live::$kind(object) => Node::$kind(Rc::downgrade(object)), )*
+                    }
+                }
+            }
+        };
+    }
+
+    nodes!(
+        Environment(RefCell<Environment>),
+        Instance(RefCell<Instance>),
+        List(RefCell<Vec<Object>>),
+        Map(RefCell<Map>),
+        Class(Class),
+        Closure(Closure),
+        Upvalue(RefCell<Upvalue>)
+    );
+
+    impl Live {
+        /// Calls `edge` with the address of everything this object holds a
+        /// strong reference to. Returns false if the object is being
+        /// changed and could not be looked into.
+        fn edges(&self, edge: &mut dyn FnMut(*const ())) -> bool {
+            match self {
+                Live::Environment(environment) => {
+                    let Ok(environment) = environment.try_borrow() else { return false };
+                    if let Some(enclosing) = &environment.enclosing {
+                        edge(address(enclosing));
+                    }
+                    environment.objects().for_each(|object| object_edges(object, edge));
+                }
+                Live::Instance(instance) => {
+                    let Ok(instance) = instance.try_borrow() else { return false };
+                    edge(address(&instance.class));
+                    instance.fields().for_each(|object| object_edges(object, edge));
+                }
+                Live::List(list) => {
+                    let Ok(list) = list.try_borrow() else { return false };
+                    list.iter().for_each(|object| object_edges(object, edge));
+                }
+                Live::Map(map) => {
+                    let Ok(map) = map.try_borrow() else { return false };
+                    map.iter().for_each(|(_, object)| object_edges(object, edge));
+                }
+                Live::Class(class) => {
+                    if let Some(superclass) = class.superclass() {
+                        edge(address(superclass));
+                    }
+                    class.methods().for_each(|method| function_edges(method, edge));
+                }
+                Live::Closure(closure) => closure.upvalues.iter().for_each(|upvalue| edge(address(upvalue))),
+                Live::Upvalue(upvalue) => {
+                    let Ok(upvalue) = upvalue.try_borrow() else { return false };
+                    if let Upvalue::Closed(object) = &*upvalue {
+                        object_edges(object, edge);
+                    }
+                }
+            }
+            true
+        }
+
+        /// Drops everything a garbage object holds. Classes and closures
+        /// cannot change, but no cycle runs through them alone.
+        fn clear(&self) {
+            match self {
+                Live::Environment(environment) => {
+                    if let Ok(mut environment) = environment.try_borrow_mut() {
+                        *environment = Environment::new();
+                    }
+                }
+                Live::Instance(instance) => {
+                    if let Ok(mut instance) = instance.try_borrow_mut() {
+                        instance.clear();
+                    }
+                }
+                Live::List(list) => {
+                    if let Ok(mut list) = list.try_borrow_mut() {
+                        list.clear();
+                    }
+                }
+                Live::Map(map) => {
+                    if let Ok(mut map) = map.try_borrow_mut() {
+                        *map = Map::new();
+                    }
+                }
+                Live::Upvalue(upvalue) => {
+                    if let Ok(mut upvalue) = upvalue.try_borrow_mut() {
+                        *upvalue = Upvalue::Closed(Object::Null);
+                    }
+                }
+                Live::Class(_) | Live::Closure(_) => (),
+            }
+        }
+    }
+
+    fn address<T>(object: &Rc<T>) -> *const () {
+        Rc::as_ptr(object) as *const ()
+    }
+
+    fn object_edges(object: &Object, edge: &mut dyn FnMut(*const ())) {
+        match object {
+            Object::Callable(function) => function_edges(function, edge),
+            Object::Class(class) => edge(address(class)),
+            Object::Instance(instance) => edge(address(instance)),
+            Object::List(list) => edge(address(list)),
+            Object::Map(map) => edge(address(map)),
+            Object::Boolean(_) | Object::Null | Object::Number(_) | Object::String(_) => (),
+        }
+    }
+
+    fn function_edges(function: &Function, edge: &mut dyn FnMut(*const ())) {
+        match function {
+            Function::Native { .. } => (),
+            Function::User { closure, .. } => edge(address(closure)),
+            Function::Closure { closure, receiver } => {
+                edge(address(closure));
+                if let Some(receiver) = receiver {
+                    edge(address(receiver));
+                }
+            }
+        }
+    }
+
+    /// Puts a new object on the heap where the collector can see it,
+    /// collecting first if enough has been allocated since last time.
+    pub fn track<T: Traced>(value: T) -> Rc<T> {
+        let value = Rc::new(value);
+        let due = HEAP.with(|heap| {
+            let mut heap = heap.borrow_mut();
+            heap.nodes.push(T::node(&value));
+            heap.nodes.len() >= heap.threshold
+        });
+        if due {
+            collect();
+        }
+        value
+    }
+
+    /// Frees every heap object that is only kept alive by reference cycles
+    /// and returns how many there were.
+    ///
+    /// Nothing needs to say where the roots are: an object referenced more
+    /// often than the heap itself accounts for is held from outside it, by
+    /// an interpreter, a VM stack or any other Rust code, and is alive
+    /// along with everything it references.
+    pub fn collect() -> usize {
+        let nodes = HEAP.with(|heap| mem::take(&mut heap.borrow_mut().nodes));
+        let live: Vec<Live> = nodes.iter().filter_map(Node::upgrade).collect();
+        drop(nodes);
+
+        let index: HashMap<*const (), usize> =
+            live.iter().enumerate().map(|(position, object)| (object.address(), position)).collect();
+        let mut internal = vec![0; live.len()];
+        let mut references = Vec::with_capacity(live.len());
+        let mut pending = Vec::new();
+        for (position, object) in live.iter().enumerate() {
+            let mut targets = Vec::new();
+            let traced = object.edges(&mut |target| {
+                if let Some(&target) = index.get(&target) {
+                    internal[target] += 1;
+                    targets.push(target);
+                }
+            });
+            if !traced {
+                pending.push(position);
+            }
+            references.push(targets);
+        }
+
+        // One reference to each object is the collector's own.
+        pending.extend((0..live.len()).filter(|&position| live[position].strong_count() - 1 > internal[position]));
+        let mut reachable = vec![false; live.len()];
+        while let Some(position) = pending.pop() {
+            if !reachable[position] {
+                reachable[position] = true;
+                pending.extend(references[position].iter().filter(|&&target| !reachable[target]));
+            }
+        }
+
+        let mut freed = 0;
+        let mut survivors = Vec::new();
+        for (object, reachable) in live.iter().zip(reachable) {
+            if reachable {
+                survivors.push(object.downgrade());
+            } else {
+                object.clear();
+                freed += 1;
+            }
+        }
+
+        HEAP.with(|heap| {
+            let mut heap = heap.borrow_mut();
+            heap.threshold = MIN_THRESHOLD.max(survivors.len() * 2);
+            survivors.append(&mut heap.nodes);
+            heap.nodes = survivors;
+            heap.stats.collections += 1;
+            heap.stats.freed += freed;
+        });
+        // The garbage itself is freed here, once nothing holds on to it.
+        drop(live);
+        freed
+    }
+
+    pub fn stats() -> Stats {
+        HEAP.with(|heap| {
+            let heap = heap.borrow();
+            Stats {
+                live: heap.nodes.iter().filter(|node| node.is_alive()).count(),
+                ..heap.stats
+            }
+        })
+    }
+}
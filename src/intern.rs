@@ -0,0 +1,25 @@
+pub mod intern {
+    use std::{collections::HashSet, rc::Rc};
+
+    /// Hands out one shared copy of each distinct string, so that values
+    /// made from the same text compare by pointer and never allocate again.
+    #[derive(Debug, Default)]
+    pub struct Interner {
+        strings: HashSet<Rc<str>>,
+    }
+
+    impl Interner {
+        pub fn new() -> Self {
+            Interner { strings: HashSet::new() }
+        }
+
+        pub fn intern(&mut self, string: &str) -> Rc<str> {
+            if let Some(interned) = self.strings.get(string) {
+                return Rc::clone(interned);
+            }
+            let interned: Rc<str> = Rc::from(string);
+            self.strings.insert(Rc::clone(&interned));
+            interned
+        }
+    }
+}
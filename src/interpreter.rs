@@ -1,25 +1,116 @@
 pub mod interpreter {
-    use std::{cell::RefCell, collections::HashMap, rc::Rc};
+    use std::{cell::RefCell, collections::HashMap, mem, rc::Rc};
 
-    use crate::{environment::environment::{self, Environment}, expr::expr::{self, Expr, LiteralValue}, function::function::Function, object::object::Object, parser::parser::Error, stmt::stmt::{self, Stmt}, token::token::{Token, TokenType}};
+    use crate::{class::class::{Class, Instance}, environment::environment::Environment, expr::expr::{self, Expr, ExprId, LiteralValue}, function::function::{Function, Runtime}, gc::gc, map::map::{Key, Map}, object::object::Object, parser::parser::Error, stdlib::stdlib, stmt::stmt::{self, Stmt}, token::token::{Token, TokenType}};
+
+    /// How many scopes out each local variable expression of one program
+    /// refers to, and its slot in that scope.
+    pub type Locals = Rc<HashMap<ExprId, (usize, usize)>>;
 
     pub struct Interpreter {
         pub globals: Rc<RefCell<Environment>>,
         environment: Rc<RefCell<Environment>>,
-        locals: HashMap<Token, usize>,
+        locals: Locals,
+        frames: Vec<Frame>,
+        max_depth: usize,
+    }
+
+    /// How a statement finished: by running to its end, or by a `return`,
+    /// `break` or `continue` that has to leave every enclosing statement up
+    /// to the function or loop it belongs to.
+    #[derive(Debug)]
+    pub enum Flow {
+        Normal,
+        Return(Object),
+        Break,
+        Continue,
+    }
+
+    /// A call in progress: what was called and the closing parenthesis of
+    /// the call.
+    #[derive(Clone, Debug)]
+    pub struct Frame {
+        pub function: String,
+        pub call_site: Token,
+        pub native: bool,
     }
 
     impl Interpreter {
         pub fn new() -> Self {
-            let globals = Rc::new(RefCell::new(Environment::new()));
+            let globals = gc::track(RefCell::new(Environment::new()));
+            stdlib::define(&mut globals.borrow_mut());
             Interpreter {
                 globals: Rc::clone(&globals),
                 environment: Rc::clone(&globals),
-                locals: HashMap::new(),
+                locals: Locals::default(),
+                frames: Vec::new(),
+                max_depth: Self::DEFAULT_MAX_DEPTH,
             }
         }
 
-        pub fn interpret(&mut self, statements: &Vec<Stmt>) -> Result<(), Error> {
+        /// How many calls may be in progress at once unless
+        /// `set_max_depth` says otherwise.
+        pub const DEFAULT_MAX_DEPTH: usize = 10_000;
+
+        /// Limits how deeply calls may nest before the script fails with a
+        /// stack overflow instead of overflowing the native stack.
+        pub fn set_max_depth(&mut self, max_depth: usize) {
+            self.max_depth = max_depth;
+        }
+
+        /// Calls any callable value, checking the number of arguments first.
+        pub fn call(&mut self, callee: &Object, arguments: &[Object], paren: &Token) -> Result<Object, Error> {
+            let arity = match callee {
+                Object::Callable(function) => function.arity(),
+                Object::Class(class) => class.arity(),
+                _ => {
+                    return Err(Error::runtime(paren, "Can only call functions and classes."))
+                }
+            };
+
+            if !arity.accepts(arguments.len()) {
+                return Err(Error::runtime(paren, format!("Expected {} but got {}.", arity, arguments.len())));
+            }
+
+            if self.frames.len() >= self.max_depth {
+                return Err(Error::runtime(paren, "Stack overflow."));
+            }
+
+            self.frames.push(Frame {
+                function: match callee {
+                    Object::Callable(function) => function.name().to_string(),
+                    Object::Class(class) => class.name.clone(),
+                    _ => unreachable!(),
+                },
+                call_site: paren.clone(),
+                native: matches!(callee, Object::Callable(Function::Native { .. })),
+            });
+
+            let mut result = match callee {
+                Object::Callable(function) => function.call(self, arguments),
+                Object::Class(class) => {
+                    let instance = Object::Instance(gc::track(RefCell::new(Instance::new(class))));
+                    match class.find_method("init") {
+                        Some(initializer) => initializer.bind(instance.clone()).call(self, arguments).map(|_| instance),
+                        None => Ok(instance),
+                    }
+                }
+                _ => unreachable!(),
+            };
+
+            // The innermost call an error leaves records the whole stack.
+            if let Err(Error::Runtime { trace, .. }) = &mut result {
+                if trace.is_empty() {
+                    trace.clone_from(&self.frames);
+                }
+            }
+
+            self.frames.pop();
+            result
+        }
+
+
+        pub fn interpret(&mut self, statements: &[Stmt]) -> Result<(), Error> {
             for statement in statements {
                 self.execute(statement)?;
             }
@@ -31,26 +122,39 @@ pub mod interpreter {
             expression.accept(self)
         }
 
-        fn execute(&mut self, statement: &Stmt) -> Result<(), Error> {
+        fn execute(&mut self, statement: &Stmt) -> Result<Flow, Error> {
             statement.accept(self)
         }
 
-        pub fn resolve(&mut self, name: &Token, depth: usize) {
-            self.locals.insert(name.clone(), depth);
+        /// Takes the scope depths and slots the resolver found for local
+        /// variables of the program about to run. Functions keep the table
+        /// of the program that declared them, so it is dropped along with
+        /// the last of them rather than kept for the whole session.
+        pub fn resolve(&mut self, locals: HashMap<ExprId, (usize, usize)>) {
+            self.locals = Rc::new(locals);
+        }
+
+        /// Switches to the resolved locals of another program, returning
+        /// the ones in use until now.
+        pub fn replace_locals(&mut self, locals: Locals) -> Locals {
+            mem::replace(&mut self.locals, locals)
         }
 
         pub fn execute_block(
             &mut self,
-            statements: &Vec<Stmt>,
+            statements: &[Stmt],
             environment: Rc<RefCell<Environment>>
-        ) -> Result<(), Error> {
+        ) -> Result<Flow, Error> {
             let previous = self.environment.clone();
-            let steps = || -> Result<(), Error> {
+            let steps = || -> Result<Flow, Error> {
                 self.environment = environment;
                 for statement in statements {
-                    self.execute(statement)?
+                    match self.execute(statement)? {
+                        Flow::Normal => (),
+                        flow => return Ok(flow),
+                    }
                 }
-                Ok(())
+                Ok(Flow::Normal)
             };
 
             let result = steps();
@@ -60,15 +164,31 @@ pub mod interpreter {
         }
 
         fn number_operand_error<R>(&self, operator: &Token) -> Result<R, Error> {
-            Err(Error::Runtime {
-                token: operator.clone(),
-                message: "Operand must be a number.".to_string(),
-            })
+            Err(Error::runtime(operator, "Operand must be a number."))
         }
 
-        fn look_up_variable(&self, name: &Token) -> Result<Object, Error> {
-            if let Some(distance) = self.locals.get(name) {
-                self.environment.borrow().get_at(*distance, &name.lexeme)
+        fn list_index(&self, length: usize, index: &Object, bracket: &Token) -> Result<usize, Error> {
+            stdlib::list::resolve_index(length, index).map_err(|message| Error::runtime(bracket, message))
+        }
+
+        fn map_key(&self, key: &Object, token: &Token) -> Result<Key, Error> {
+            Key::from_object(key).map_err(|message| Error::runtime(token, message))
+        }
+
+        /// Declares a variable in the current scope: by name at the top
+        /// level, and otherwise in the next slot, which is the one the
+        /// resolver numbered it with.
+        fn declare(&mut self, name: &Token, value: Object) {
+            if Rc::ptr_eq(&self.environment, &self.globals) {
+                self.globals.borrow_mut().define(name.key().into_owned(), value);
+            } else {
+                self.environment.borrow_mut().push(value);
+            }
+        }
+
+        fn look_up_variable(&self, id: ExprId, name: &Token) -> Result<Object, Error> {
+            if let Some(&(distance, slot)) = self.locals.get(&id) {
+                self.environment.borrow().get_at(distance, slot)
             } else {
                 self.globals.borrow().get(name)
             }
@@ -81,21 +201,34 @@ pub mod interpreter {
         fn is_truthy(&self, object: &Object) -> bool {
             match object {
                 Object::Null => false,
-                Object::Boolean(b) => b.clone(),
+                Object::Boolean(b) => *b,
                 _ => true,
             }
         }
 
         fn stringify(&self, object: &Object) -> String {
-            match object {
-                Object::Boolean(b) => b.to_string(),
-                Object::Callable(f) => f.to_string(),
-                Object::Null => "nil".to_string(),
-                Object::Number(n) => n.to_string(),
-                Object::String(s) => s.to_string(),
-            }
+            object.to_string()
+        }
+
+    }
+
+    impl Runtime for Interpreter {
+        fn call(&mut self, callee: &Object, arguments: &[Object], paren: &Token) -> Result<Object, Error> {
+            Interpreter::call(self, callee, arguments, paren)
+        }
+
+        fn call_site(&self) -> Token {
+            self.frames
+                .last()
+                .map(|frame| frame.call_site.clone())
+                .expect("Call site outside of a native function.")
         }
+    }
 
+    impl Default for Interpreter {
+        fn default() -> Self {
+            Self::new()
+        }
     }
 
     impl expr::Visitor<Object> for Interpreter {
@@ -144,12 +277,9 @@ pub mod interpreter {
                         Ok(Object::Number(left_number + right_number))
                     }
                     (Object::String(left_string), Object::String(right_string)) => {
-                        Ok(Object::String(left_string.clone() + &right_string))
+                        Ok(Object::String([&*left_string, &*right_string].concat().into()))
                     }
-                    _ => Err(Error::Runtime {
-                        token: operator.clone(),
-                        message: "Operands must be two numbers or two strings.".to_string(),
-                    }),
+                    _ => Err(Error::runtime(operator, "Operands must be two numbers or two strings.")),
                 },
                 TokenType::Slash => match (l, r) {
                     (Object::Number(left_number), Object::Number(right_number)) => {
@@ -173,36 +303,23 @@ pub mod interpreter {
             &mut self,
             callee: &Expr,
             paren: &Token,
-            arguments: &Vec<Expr>,
+            arguments: &[Expr],
         ) -> Result<Object, Error> {
             let callee_value = self.evaluate(callee)?;
 
             let argument_values: Result<Vec<Object>, Error> = arguments
-                .into_iter()
+                .iter()
                 .map(|expr| self.evaluate(expr))
                 .collect();
             let args = argument_values?;
 
-            match callee_value {
-                Object::Callable(function) => {
-                    let args_size = args.len();
-                    if args_size != function.arity() {
-                        Err(Error::Runtime {
-                            token: paren.clone(),
-                            message: format!(
-                                "Expected {} arguments but got {}.",
-                                function.arity(),
-                                args_size
-                            ),
-                        })
-                    } else {
-                        function.call(self, &args)
-                    }
-                }
-                _ => Err(Error::Runtime {
-                    token: paren.clone(),
-                    message: "Can only call functions.".to_string(),
-                }),
+            self.call(&callee_value, &args, paren)
+        }
+
+        fn visit_get_expr(&mut self, object: &Expr, name: &Token) -> Result<Object, Error> {
+            match self.evaluate(object)? {
+                Object::Instance(instance) => Instance::get(&instance, name),
+                _ => Err(Error::runtime(name, "Only instances have properties.")),
             }
         }
 
@@ -210,15 +327,52 @@ pub mod interpreter {
             self.evaluate(expr)
         }
 
+        fn visit_index_expr(&mut self, object: &Expr, bracket: &Token, index: &Expr) -> Result<Object, Error> {
+            let object = self.evaluate(object)?;
+            let index = self.evaluate(index)?;
+
+            match object {
+                Object::List(list) => {
+                    let list = list.borrow();
+                    let position = self.list_index(list.len(), &index, bracket)?;
+                    Ok(list[position].clone())
+                }
+                Object::Map(map) => {
+                    let key = self.map_key(&index, bracket)?;
+                    let value = map.borrow().get(&key).cloned();
+                    value.ok_or_else(|| Error::runtime(bracket, format!("Undefined key '{}'.", index)))
+                }
+                _ => Err(Error::runtime(bracket, "Can only index lists and maps.")),
+            }
+        }
+
+        fn visit_list_expr(&mut self, _bracket: &Token, elements: &[Expr]) -> Result<Object, Error> {
+            let values: Result<Vec<Object>, Error> = elements
+                .iter()
+                .map(|element| self.evaluate(element))
+                .collect();
+            Ok(Object::list(values?))
+        }
+
         fn visit_literal_expr(&mut self, value: &LiteralValue) -> Result<Object, Error> {
             match value {
-                LiteralValue::Boolean(b) => Ok(Object::Boolean(b.clone())),
+                LiteralValue::Boolean(b) => Ok(Object::Boolean(*b)),
                 LiteralValue::Null => Ok(Object::Null),
-                LiteralValue::Number(n) => Ok(Object::Number(n.clone())),
+                LiteralValue::Number(n) => Ok(Object::Number(*n)),
                 LiteralValue::String(s) => Ok(Object::String(s.clone())),
             }
         }
 
+        fn visit_map_expr(&mut self, brace: &Token, entries: &[(Expr, Expr)]) -> Result<Object, Error> {
+            let mut map = Map::new();
+            for (key, value) in entries {
+                let key = self.evaluate(key)?;
+                let key = self.map_key(&key, brace)?;
+                map.insert(key, self.evaluate(value)?);
+            }
+            Ok(Object::map(map))
+        }
+
         fn visit_logical_expr(
             &mut self,
             left: &Expr,
@@ -239,12 +393,75 @@ pub mod interpreter {
             self.evaluate(right)
         }
 
+        fn visit_set_expr(&mut self, object: &Expr, name: &Token, value: &Expr) -> Result<Object, Error> {
+            match self.evaluate(object)? {
+                Object::Instance(instance) => {
+                    let v = self.evaluate(value)?;
+                    instance.borrow_mut().set(name, v.clone());
+                    Ok(v)
+                }
+                _ => Err(Error::runtime(name, "Only instances have fields.")),
+            }
+        }
+
+        fn visit_super_expr(&mut self, id: ExprId, _keyword: &Token, method: &Token) -> Result<Object, Error> {
+            let (distance, slot) = *self
+                .locals
+                .get(&id)
+                .expect("'super' should have been resolved.");
+
+            let superclass = match self.environment.borrow().get_at(distance, slot)? {
+                Object::Class(class) => class,
+                _ => unreachable!(),
+            };
+            // 'this' is alone in the scope just inside the one with 'super'.
+            let object = self.environment.borrow().get_at(distance - 1, 0)?;
+
+            match superclass.find_method(&method.key()) {
+                Some(function) => Ok(Object::Callable(function.bind(object))),
+                None => Err(Error::runtime(method, format!("Undefined property '{}'.", method.lexeme))),
+            }
+        }
+
+        fn visit_set_index_expr(
+            &mut self,
+            object: &Expr,
+            bracket: &Token,
+            index: &Expr,
+            value: &Expr,
+        ) -> Result<Object, Error> {
+            let object = self.evaluate(object)?;
+            let index = self.evaluate(index)?;
+            let v = self.evaluate(value)?;
+
+            match object {
+                Object::List(list) => {
+                    let position = self.list_index(list.borrow().len(), &index, bracket)?;
+                    list.borrow_mut()[position] = v.clone();
+                    Ok(v)
+                }
+                Object::Map(map) => {
+                    let key = self.map_key(&index, bracket)?;
+                    map.borrow_mut().insert(key, v.clone());
+                    Ok(v)
+                }
+                _ => Err(Error::runtime(bracket, "Can only index lists and maps.")),
+            }
+        }
+
+        fn visit_this_expr(&mut self, id: ExprId, keyword: &Token) -> Result<Object, Error> {
+            match self.locals.get(&id) {
+                Some(&(distance, slot)) => self.environment.borrow().get_at(distance, slot),
+                None => self.globals.borrow().get(keyword),
+            }
+        }
+
         fn visit_unary_expr(&mut self, operator: &Token, right: &Expr) -> Result<Object, Error> {
             let right = self.evaluate(right)?;
 
             match &operator.token_type {
                 TokenType::Minus => match right {
-                    Object::Number(n) => Ok(Object::Number(-n.clone())),
+                    Object::Number(n) => Ok(Object::Number(-n)),
                     _ => self.number_operand_error(operator),
                 },
                 TokenType::Bang => Ok(Object::Boolean(!self.is_truthy(&right))), 
@@ -252,110 +469,169 @@ pub mod interpreter {
             }
         }
 
-        fn visit_variable_expr(&mut self, name: &Token) -> Result<Object, Error> {
-            self.look_up_variable(name)
+        fn visit_variable_expr(&mut self, id: ExprId, name: &Token) -> Result<Object, Error> {
+            self.look_up_variable(id, name)
         }
 
-        fn visit_assign_expr(&mut self, name: &Token, value: &Expr) -> Result<Object, Error> {
+        fn visit_assign_expr(&mut self, id: ExprId, name: &Token, value: &Expr) -> Result<Object, Error> {
             let v = self.evaluate(value)?;
 
-            if let Some(distance) = self.locals.get(name) {
+            if let Some(&(distance, slot)) = self.locals.get(&id) {
                 self.environment
                     .borrow_mut()
-                    .assign_at(*distance, name, v.clone())?;
+                    .assign_at(distance, slot, v.clone())?;
             } else {
-                self.environment.borrow_mut().assign(name, v.clone())?;
+                self.globals.borrow_mut().assign(name, v.clone())?;
             }
             Ok(v)
         }
 
     }
 
-    impl stmt::Visitor<()> for Interpreter {
-        fn visit_block_stmt(&mut self, statements: &Vec<Stmt>) -> Result<(), Error> {
-            self.execute_block(
-                statements,
-                Rc::new(RefCell::new(Environment::from(&self.environment))),
-            );
-            Ok(())
+    impl stmt::Visitor<Flow> for Interpreter {
+        fn visit_block_stmt(&mut self, statements: &[Stmt]) -> Result<Flow, Error> {
+            self.execute_block(statements, gc::track(RefCell::new(Environment::from(&self.environment))))
         }
 
-        fn visit_expression_stmt(&mut self, expression: &Expr) -> Result<(), Error> {
+        fn visit_break_stmt(&mut self, _keyword: &Token) -> Result<Flow, Error> {
+            Ok(Flow::Break)
+        }
+
+        fn visit_class_stmt(
+            &mut self,
+            name: &Token,
+            superclass: &Option<Expr>,
+            methods: &[Stmt],
+        ) -> Result<Flow, Error> {
+            let superclass = match superclass {
+                Some(expression) => match self.evaluate(expression)? {
+                    Object::Class(class) => Some(class),
+                    _ => {
+                        let token = match expression {
+                            Expr::Variable { name, .. } => name.clone(),
+                            _ => name.clone(),
+                        };
+                        return Err(Error::runtime(&token, "Superclass must be a class."));
+                    }
+                },
+                None => None,
+            };
+
+            // Methods of a subclass close over a scope holding 'super'.
+            let enclosing = Rc::clone(&self.environment);
+            if let Some(ref class) = superclass {
+                self.environment = gc::track(RefCell::new(Environment::from(&enclosing)));
+                self.environment
+                    .borrow_mut()
+                    .push(Object::Class(Rc::clone(class)));
+            }
+
+            let mut class_methods: HashMap<String, Function> = HashMap::new();
+            for method in methods {
+                if let Stmt::Function { name, params, body, .. } = method {
+                    let function = Function::User {
+                        name: name.clone(),
+                        params: Rc::clone(params),
+                        body: Rc::clone(body),
+                        closure: Rc::clone(&self.environment),
+                        locals: Rc::clone(&self.locals),
+                        is_initializer: name.key() == "init",
+                    };
+                    class_methods.insert(name.key().into_owned(), function);
+                }
+            }
+
+            self.environment = enclosing;
+
+            // No other declaration can come between, so the class still
+            // gets the slot the resolver gave its name.
+            let class = Object::Class(gc::track(Class::new(name.lexeme.clone(), superclass, class_methods)));
+            self.declare(name, class);
+            Ok(Flow::Normal)
+        }
+
+        fn visit_continue_stmt(&mut self, _keyword: &Token) -> Result<Flow, Error> {
+            Ok(Flow::Continue)
+        }
+
+        fn visit_expression_stmt(&mut self, expression: &Expr) -> Result<Flow, Error> {
             self.evaluate(expression)?;
-            Ok(())
+            Ok(Flow::Normal)
         }
 
         fn visit_function_stmt(
             &mut self,
             name: &Token,
-            params: &Vec<Token>,
-            body: &Vec<Stmt>,
-        ) -> Result<(), Error> {
+            params: &Rc<[Token]>,
+            body: &Rc<[Stmt]>,
+        ) -> Result<Flow, Error> {
             let function = Function::User {
                 name: name.clone(),
-                params: params.clone(),
-                body: body.clone(),
+                params: Rc::clone(params),
+                body: Rc::clone(body),
                 closure: Rc::clone(&self.environment),
+                locals: Rc::clone(&self.locals),
                 is_initializer: false,
             };
-            self.environment
-                .borrow_mut()
-                .define(name.lexeme.clone(), Object::Callable(function));
-            Ok(())
+            self.declare(name, Object::Callable(function));
+            Ok(Flow::Normal)
         }
         fn visit_if_stmt(
             &mut self,
             condition: &Expr,
             else_branch: &Option<Stmt>,
             then_branch: &Stmt,
-        ) -> Result<(), Error> {
+        ) -> Result<Flow, Error> {
             let condition_value = self.evaluate(condition)?;
             if self.is_truthy(&condition_value) {
-                self.execute(then_branch)?;
+                self.execute(then_branch)
             } else if let Some(other) = else_branch {
-                self.execute(other)?;
+                self.execute(other)
+            } else {
+                Ok(Flow::Normal)
             }
-
-            Ok(())
         }
 
-        fn visit_print_stmt(&mut self, expression: &Expr) -> Result<(), Error> {
+        fn visit_print_stmt(&mut self, expression: &Expr) -> Result<Flow, Error> {
             let value = self.evaluate(expression)?;
             println!("{}", self.stringify(&value));
-            Ok(())
+            Ok(Flow::Normal)
         }
 
-        fn visit_return_stmt(&mut self, _keyword: &Token, value: &Option<Expr>) -> Result<(), Error> {
+        fn visit_return_stmt(&mut self, _keyword: &Token, value: &Option<Expr>) -> Result<Flow, Error> {
             let return_value: Object = value
                 .as_ref()
                 .map(|v| self.evaluate(v))
                 .unwrap_or(Ok(Object::Null))?;
 
-            Err(Error::Return {
-                value: return_value,
-            })
+            Ok(Flow::Return(return_value))
         }
 
-        fn visit_var_stmt(&mut self, name: &Token, initializer: &Option<Expr>) -> Result<(), Error> {
+        fn visit_var_stmt(&mut self, name: &Token, initializer: &Option<Expr>) -> Result<Flow, Error> {
             let value: Object = initializer
                 .as_ref()
                 .map(|i| self.evaluate(i))
                 .unwrap_or(Ok(Object::Null))?;
 
-            self.environment
-                .borrow_mut()
-                .define(name.lexeme.clone(), value);
-            Ok(())
+            self.declare(name, value);
+            Ok(Flow::Normal)
         }
 
-        fn visit_while_stmt(&mut self, condition: &Expr, body: &Stmt) -> Result<(), Error> {
+        fn visit_while_stmt(&mut self, condition: &Expr, body: &Stmt, increment: &Option<Expr>) -> Result<Flow, Error> {
             let mut value = self.evaluate(condition)?;
             while self.is_truthy(&value) {
-                self.execute(body)?;
+                match self.execute(body)? {
+                    Flow::Normal | Flow::Continue => (),
+                    Flow::Break => break,
+                    flow @ Flow::Return(_) => return Ok(flow),
+                }
+                if let Some(increment) = increment {
+                    self.evaluate(increment)?;
+                }
                 value = self.evaluate(condition)?
             }
 
-            Ok(())
+            Ok(Flow::Normal)
         }
     }
 
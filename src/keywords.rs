@@ -0,0 +1,176 @@
+pub mod keywords {
+    use std::fmt;
+
+    use crate::{token::token::TokenType, unicode::unicode};
+
+    /// A reserved word together with its emoji spelling. Both spellings scan
+    /// to the same `TokenType`; the emoji is stored fully qualified (with its
+    /// variation selector) but matched with or without it.
+    #[derive(Debug)]
+    pub struct Keyword {
+        pub word: &'static str,
+        pub emoji: &'static str,
+        pub token_type: TokenType,
+    }
+
+    /// Every reserved word of the language, shared by the scanner, the
+    /// formatter and error messages.
+    ///
+    /// | word     | emoji |
+    /// |----------|-------|
+    /// | `and`    | 🤝    |
+    /// | `break`  | 🛑    |
+    /// | `class`  | 🏛️    |
+    /// | `continue` | ⏭️  |
+    /// | `else`   | ↪️    |
+    /// | `false`  | 👎    |
+    /// | `for`    | 🔂    |
+    /// | `fun`    | 🧑‍💻    |
+    /// | `if`     | ❓    |
+    /// | `nil`    | 🕳️    |
+    /// | `or`     | 🔀    |
+    /// | `print`  | 🖨️    |
+    /// | `return` | ↩️    |
+    /// | `super`  | 🦸    |
+    /// | `this`   | 🫵    |
+    /// | `true`   | 👍    |
+    /// | `var`    | 📦    |
+    /// | `while`  | 🔁    |
+    pub static KEYWORDS: [Keyword; 18] = [
+        Keyword { word: "and", emoji: "🤝", token_type: TokenType::And },
+        Keyword { word: "break", emoji: "🛑", token_type: TokenType::Break },
+        Keyword { word: "class", emoji: "🏛️", token_type: TokenType::Class },
+        Keyword { word: "continue", emoji: "⏭️", token_type: TokenType::Continue },
+        Keyword { word: "else", emoji: "↪️", token_type: TokenType::Else },
+        Keyword { word: "false", emoji: "👎", token_type: TokenType::False },
+        Keyword { word: "for", emoji: "🔂", token_type: TokenType::For },
+        Keyword { word: "fun", emoji: "🧑‍💻", token_type: TokenType::Fun },
+        Keyword { word: "if", emoji: "❓", token_type: TokenType::If },
+        Keyword { word: "nil", emoji: "🕳️", token_type: TokenType::Nil },
+        Keyword { word: "or", emoji: "🔀", token_type: TokenType::Or },
+        Keyword { word: "print", emoji: "🖨️", token_type: TokenType::Print },
+        Keyword { word: "return", emoji: "↩️", token_type: TokenType::Return },
+        Keyword { word: "super", emoji: "🦸", token_type: TokenType::Super },
+        Keyword { word: "this", emoji: "🫵", token_type: TokenType::This },
+        Keyword { word: "true", emoji: "👍", token_type: TokenType::True },
+        Keyword { word: "var", emoji: "📦", token_type: TokenType::Var },
+        Keyword { word: "while", emoji: "🔁", token_type: TokenType::While },
+    ];
+
+    /// An operator or delimiter together with its emoji spelling. Tokens keep
+    /// whichever spelling was typed as their lexeme.
+    #[derive(Debug)]
+    pub struct Operator {
+        pub symbol: &'static str,
+        pub emoji: &'static str,
+        pub token_type: TokenType,
+    }
+
+    /// Every operator and delimiter of the language. `❗🟰` is two emoji
+    /// long, just as `!=` is two characters.
+    ///
+    /// | symbol | emoji | symbol | emoji |
+    /// |--------|-------|--------|-------|
+    /// | `(`    | 🌜    | `!`    | ❗    |
+    /// | `)`    | 🌛    | `!=`   | ❗🟰  |
+    /// | `{`    | 👉    | `=`    | ⬅️    |
+    /// | `}`    | 👈    | `==`   | 🟰    |
+    /// | `,`    | 🔹    | `>`    | ▶️    |
+    /// | `.`    | 🔸    | `>=`   | ⏩    |
+    /// | `-`    | ➖    | `<`    | ◀️    |
+    /// | `+`    | ➕    | `<=`   | ⏪    |
+    /// | `;`    | 🔚    | `/`    | ➗    |
+    /// | `*`    | ✖️    | `[`    | 🫸    |
+    /// | `]`    | 🫷    | `:`    | ➡️    |
+    pub static OPERATORS: [Operator; 22] = [
+        Operator { symbol: "(", emoji: "🌜", token_type: TokenType::LeftParen },
+        Operator { symbol: ")", emoji: "🌛", token_type: TokenType::RightParen },
+        Operator { symbol: "{", emoji: "👉", token_type: TokenType::LeftBrace },
+        Operator { symbol: "}", emoji: "👈", token_type: TokenType::RightBrace },
+        Operator { symbol: "[", emoji: "🫸", token_type: TokenType::LeftBracket },
+        Operator { symbol: "]", emoji: "🫷", token_type: TokenType::RightBracket },
+        Operator { symbol: ":", emoji: "➡️", token_type: TokenType::Colon },
+        Operator { symbol: ",", emoji: "🔹", token_type: TokenType::Comma },
+        Operator { symbol: ".", emoji: "🔸", token_type: TokenType::Dot },
+        Operator { symbol: "-", emoji: "➖", token_type: TokenType::Minus },
+        Operator { symbol: "+", emoji: "➕", token_type: TokenType::Plus },
+        Operator { symbol: ";", emoji: "🔚", token_type: TokenType::Semicolon },
+        Operator { symbol: "/", emoji: "➗", token_type: TokenType::Slash },
+        Operator { symbol: "*", emoji: "✖️", token_type: TokenType::Star },
+        Operator { symbol: "!", emoji: "❗", token_type: TokenType::Bang },
+        Operator { symbol: "!=", emoji: "❗🟰", token_type: TokenType::BangEqual },
+        Operator { symbol: "=", emoji: "⬅️", token_type: TokenType::Equal },
+        Operator { symbol: "==", emoji: "🟰", token_type: TokenType::EqualEqual },
+        Operator { symbol: ">", emoji: "▶️", token_type: TokenType::Greater },
+        Operator { symbol: ">=", emoji: "⏩", token_type: TokenType::GreaterEqual },
+        Operator { symbol: "<", emoji: "◀️", token_type: TokenType::Less },
+        Operator { symbol: "<=", emoji: "⏪", token_type: TokenType::LessEqual },
+    ];
+
+    /// Finds the keyword spelled by `text`, in either form.
+    pub fn lookup(text: &str) -> Option<&'static Keyword> {
+        let stripped = unicode::strip_variation_selectors(text);
+        KEYWORDS.iter().find(|keyword| {
+            keyword.word == text || unicode::strip_variation_selectors(keyword.emoji) == stripped
+        })
+    }
+
+    pub fn for_token_type(token_type: &TokenType) -> Option<&'static Keyword> {
+        KEYWORDS.iter().find(|keyword| &keyword.token_type == token_type)
+    }
+
+    /// Finds the operator spelled by `text`, in either form.
+    pub fn lookup_operator(text: &str) -> Option<&'static Operator> {
+        let stripped = unicode::strip_variation_selectors(text);
+        OPERATORS.iter().find(|operator| {
+            operator.symbol == text || unicode::strip_variation_selectors(operator.emoji) == stripped
+        })
+    }
+
+    /// Byte length of the emoji operator at the start of `text`, or 0. The
+    /// longest spelling wins, so `❗🟰` is preferred over `❗`.
+    pub fn emoji_operator_len(text: &str) -> usize {
+        let first = unicode::emoji_len(text);
+        if first == 0 {
+            return 0;
+        }
+
+        let second = unicode::emoji_len(&text[first..]);
+        if second > 0 && lookup_operator(&text[..first + second]).is_some() {
+            first + second
+        } else if lookup_operator(&text[..first]).is_some() {
+            first
+        } else {
+            0
+        }
+    }
+
+    /// Whether `text` is a keyword or operator in its emoji spelling, and so
+    /// can never be part of an identifier.
+    pub fn is_reserved_emoji(text: &str) -> bool {
+        lookup(text).is_some() || lookup_operator(text).is_some()
+    }
+
+    /// Names a keyword or operator in diagnostics, e.g. `'print' (🖨️)`.
+    pub fn describe(token_type: &TokenType) -> String {
+        if let Some(keyword) = for_token_type(token_type) {
+            keyword.to_string()
+        } else if let Some(operator) = OPERATORS.iter().find(|operator| &operator.token_type == token_type) {
+            operator.to_string()
+        } else {
+            format!("{:?}", token_type)
+        }
+    }
+
+    impl fmt::Display for Keyword {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "'{}' ({})", self.word, self.emoji)
+        }
+    }
+
+    impl fmt::Display for Operator {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "'{}' ({})", self.symbol, self.emoji)
+        }
+    }
+}
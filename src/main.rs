@@ -1,14 +1,22 @@
-use std::{env, fs, io };
-use std::process::{exit, ExitCode};
-use std::io::{stdin, BufRead};
+#![allow(clippy::module_inception, clippy::result_large_err)]
 
-use interpreter::interpreter::Interpreter;
+use std::{fs, io};
+use std::process::exit;
+use std::io::BufRead;
+use std::thread;
+
+use interpreter::interpreter::{Frame, Interpreter};
+use formatter::formatter::{format, Spelling};
 use parser::parser::{Error, Parser};
 use resolver::resolver::Resolver;
 use scanner::scanner::Scanner;
-use token::token::{Token, TokenType};
+use diagnostic::diagnostic::{Diagnostic, Style};
+use token::token::Token;
+use compiler::compiler::compile;
+use vm::vm::Vm;
 
 pub mod token;
+pub mod span;
 pub mod scanner;
 pub mod parser;
 pub mod stmt;
@@ -17,89 +25,231 @@ pub mod interpreter;
 pub mod environment;
 pub mod object;
 pub mod function;
+pub mod class;
+pub mod stdlib;
+pub mod map;
 pub mod resolver;
+pub mod unicode;
+pub mod keywords;
+pub mod formatter;
+pub mod diagnostic;
+pub mod compiler;
+pub mod vm;
+pub mod intern;
+pub mod gc;
+
+/// Native stack one nested call takes in the tree walker, measured on the
+/// deepest paths (methods, initializers and natives calling back into
+/// scripts) plus a quarter or so of margin. Debug builds have far bigger
+/// frames.
+const STACK_PER_CALL: usize = if cfg!(debug_assertions) { 24 * 1024 } else { 6 * 1024 };
+
+/// Native stack for everything but nested calls: parsing, resolving and
+/// the top level of the script.
+const BASE_STACK: usize = 256 * 1024;
+
+/// The stack a thread gets when it does not ask for a size.
+const DEFAULT_STACK: usize = 2 * 1024 * 1024;
 
 fn main() -> Result<(), Box<dyn std::error::Error + 'static>> {
-    let args: Vec<String> = std::env::args().collect();
-    let mut emojica = Emojica::new();
-    match args.as_slice() {
+    let mut args: Vec<String> = std::env::args().collect();
+    let mut max_depth = Interpreter::DEFAULT_MAX_DEPTH;
+    let vm = match args.iter().position(|arg| arg == "--vm") {
+        Some(position) => {
+            args.remove(position);
+            true
+        }
+        None => false,
+    };
+    if let Some(position) = args.iter().position(|arg| arg == "--max-depth") {
+        match args.get(position + 1).and_then(|depth| depth.parse().ok()) {
+            Some(depth) => max_depth = depth,
+            None => usage(),
+        }
+        args.drain(position..position + 2);
+    }
+
+    // Formatting runs no code, and the VM keeps its frames on the heap, so
+    // neither needs room for nested calls.
+    if vm || args.iter().any(|arg| arg == "--emojify" || arg == "--asciify") {
+        return run_cli(&args, max_depth, vm);
+    }
+
+    // Reaching the depth limit has to happen before the native stack runs
+    // out, so run on a stack sized for it. If that much cannot be had, fall
+    // back to as many calls as a default stack holds.
+    let stack = max_depth.saturating_mul(STACK_PER_CALL).saturating_add(BASE_STACK);
+    let interpreter = match spawn(args.clone(), max_depth, vm, stack) {
+        Ok(interpreter) => interpreter,
+        Err(error) => {
+            let fallback = max_depth.min((DEFAULT_STACK - BASE_STACK) / STACK_PER_CALL);
+            eprintln!("Cannot reserve a stack for {} nested calls ({}); allowing {}.", max_depth, error, fallback);
+            spawn(args, fallback, vm, DEFAULT_STACK)?
+        }
+    };
+    match interpreter.join() {
+        Ok(result) => Ok(result?),
+        Err(_) => {
+            eprintln!("The interpreter thread stopped unexpectedly.");
+            exit(70)
+        }
+    }
+}
+
+fn spawn(args: Vec<String>, max_depth: usize, vm: bool, stack: usize) -> io::Result<thread::JoinHandle<Result<(), String>>> {
+    thread::Builder::new()
+        .stack_size(stack)
+        .spawn(move || run_cli(&args, max_depth, vm).map_err(|error| error.to_string()))
+}
+
+fn run_cli(args: &[String], max_depth: usize, vm: bool) -> Result<(), Box<dyn std::error::Error + 'static>> {
+    let engine = if vm {
+        let mut vm = Vm::new();
+        vm.set_max_depth(max_depth);
+        Engine::Vm(vm)
+    } else {
+        let mut interpreter = Interpreter::new();
+        interpreter.set_max_depth(max_depth);
+        Engine::TreeWalker(interpreter)
+    };
+    let mut emojica = Emojica::new(engine);
+    match args {
         [_, file] => match emojica.run_file(file) {
             Ok(_) => (),
-            Err(Error::Return { .. }) => unreachable!(),
-            Err(Error::Runtime { message, .. }) => {
-                eprintln!("Error: {}", message);
-                exit(70)
-            }
+            Err(Error::Runtime { .. }) => exit(70),
             Err(Error::Parse) => exit(65),
-            Err(Error::Io(_)) => unimplemented!(),
+            Err(Error::Io(error)) => cannot_read(file, &error),
         },
-        [_] => emojica.run_prompt()?,
-        _ => {
-            eprintln!("Usage: lox-rs [script]");
-            exit(64)
+        [_, flag, file] if flag == "--emojify" || flag == "--asciify" => {
+            let spelling = if flag == "--emojify" { Spelling::Emoji } else { Spelling::Ascii };
+            let source = fs::read_to_string(file).unwrap_or_else(|error| cannot_read(file, &error));
+            print!("{}", format(&source, spelling));
         }
+        [_] => emojica.run_prompt()?,
+        _ => usage(),
     }
     Ok(())
 }
 
+fn cannot_read(path: &str, error: &io::Error) -> ! {
+    eprintln!("Could not read '{}': {}", path, error);
+    exit(66)
+}
+
+fn usage() -> ! {
+    eprintln!("Usage: emojica [--vm] [--max-depth N] [--emojify | --asciify] [script]");
+    exit(64)
+}
+
+/// What runs scripts once they are resolved.
+enum Engine {
+    TreeWalker(Interpreter),
+    Vm(Vm),
+}
+
 struct Emojica {
-    interpreter: Interpreter,
+    engine: Engine,
 }
 
 impl Emojica {
-    fn new() -> Self {
-        Emojica {
-            interpreter: Interpreter::new(),
-        }
+    fn new(engine: Engine) -> Self {
+        Emojica { engine }
     }
 
     fn run_file(&mut self, path: &str) -> Result<(), Error> {
         let source = fs::read_to_string(path);
-        self.run(source?)
+        self.run(&source?, path)
     }
 
     fn run_prompt(&mut self) -> Result<(), Error> {
         let stdin = io::stdin();
         for line in stdin.lock().lines() {
-            self.run(line?);
+            let _ = self.run(&line?, "<stdin>");
             print!("> ");
         }
         Ok(())
     }
 
-  fn run(&mut self, source: String) -> Result<(), Error> {
-        let mut scanner = Scanner::new(source);
-        let tokens = scanner.scan_tokens();
+    /// Runs `source`, printing any errors it has. `name` is shown in
+    /// error messages as the place the source came from.
+    fn run(&mut self, source: &str, name: &str) -> Result<(), Error> {
+        let mut scanner = Scanner::new(source.to_string());
+        let tokens = scanner.scan_tokens().to_vec();
 
-        let mut parser = Parser::new(tokens.to_vec());
-        let statements = parser.parse()?;
+        if !scanner.diagnostics.is_empty() {
+            return Err(report(&scanner.diagnostics, name, source));
+        }
+
+        let mut parser = Parser::new(tokens);
+        let statements = parser.parse();
 
-        let mut resolver = Resolver::new(&mut self.interpreter);
+        if !parser.diagnostics.is_empty() {
+            return Err(report(&parser.diagnostics, name, source));
+        }
+
+        let mut resolver = Resolver::new();
         resolver.resolve_stmts(&statements);
 
-        if resolver.had_error {
-            return Ok(());
+        if !resolver.diagnostics.is_empty() {
+            return Err(report(&resolver.diagnostics, name, source));
         }
 
-        self.interpreter.interpret(&statements)?;
-        Ok(())
+        let result = match &mut self.engine {
+            Engine::TreeWalker(interpreter) => {
+                interpreter.resolve(resolver.locals);
+                interpreter.interpret(&statements)
+            }
+            Engine::Vm(vm) => vm.interpret(compile(&statements)),
+        };
+        result.inspect_err(|error| {
+            if let Error::Runtime { token, message, trace } = error {
+                let diagnostic = Diagnostic::error("E301", message.clone(), token.span)
+                    .with_backtrace(backtrace(token, trace, name));
+                eprint!("{}", diagnostic.render(name, source, Style::for_stderr()));
+            }
+        })
     }
-
 }
 
+/// Longer backtraces keep this many calls from the innermost end and
+/// `BACKTRACE_OUTER` from the outermost, so deep recursion stays readable.
+const BACKTRACE_INNER: usize = 10;
+const BACKTRACE_OUTER: usize = 5;
 
-fn error(line: i32, message: String) {
-   report(line, String::from(""), message);
-}
+/// Lists the calls running when an error happened, innermost first, each
+/// with the line it had reached: `at sayHi (hello.emojica:2)`.
+fn backtrace(token: &Token, trace: &[Frame], name: &str) -> Vec<String> {
+    if trace.is_empty() {
+        return Vec::new();
+    }
 
-fn report(line: i32, where_report: String, message: String) {
-    eprintln!("[line {:?}] Error {:?} : {:?}", line, where_report, message);
+    let mut lines = Vec::with_capacity(trace.len() + 1);
+    let mut line = token.line;
+    for frame in trace.iter().rev() {
+        if frame.native {
+            lines.push(format!("at {} (native)", frame.function));
+        } else {
+            lines.push(format!("at {} ({}:{})", frame.function, name, line));
+        }
+        line = frame.call_site.line;
+    }
+    lines.push(format!("at <script> ({}:{})", name, line));
+
+    if lines.len() > BACKTRACE_INNER + BACKTRACE_OUTER + 1 {
+        let omitted = lines.len() - BACKTRACE_INNER - BACKTRACE_OUTER;
+        lines.splice(
+            BACKTRACE_INNER..lines.len() - BACKTRACE_OUTER,
+            [format!("... {} more calls ...", omitted)],
+        );
+    }
+    lines
 }
 
-fn parser_error(token: &Token, message: String) {
-    if token.token_type == TokenType::Eof {
-        report(token.line, " at end".to_string(), message);
-    } else {
-        report(token.line, " at end".to_string(), message);
+/// Prints every diagnostic and gives the error that ends the run.
+fn report(diagnostics: &[Diagnostic], name: &str, source: &str) -> Error {
+    let style = Style::for_stderr();
+    for diagnostic in diagnostics {
+        eprint!("{}", diagnostic.render(name, source, style));
     }
+    Error::Parse
 }
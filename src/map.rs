@@ -0,0 +1,90 @@
+pub mod map {
+    use std::{collections::HashMap, rc::Rc};
+
+    use crate::object::object::Object;
+
+    /// The values that can be used as map keys. Numbers are keyed by their
+    /// bits, with `-0` folded into `0`, so two keys are equal exactly when
+    /// `Object::equals` says the objects are.
+    #[derive(Clone, Debug, PartialEq, Eq, Hash)]
+    pub enum Key {
+        Boolean(bool),
+        Number(u64),
+        String(Rc<str>),
+    }
+
+    impl Key {
+        pub fn from_object(object: &Object) -> Result<Key, String> {
+            match object {
+                Object::Boolean(b) => Ok(Key::Boolean(*b)),
+                Object::Number(n) if n.is_nan() => Err("NaN cannot be a map key.".to_string()),
+                Object::Number(n) => Ok(Key::Number(if *n == 0.0 { 0.0f64 } else { *n }.to_bits())),
+                Object::String(s) => Ok(Key::String(Rc::clone(s))),
+                _ => Err("Map keys must be strings, numbers or booleans.".to_string()),
+            }
+        }
+
+        pub fn to_object(&self) -> Object {
+            match self {
+                Key::Boolean(b) => Object::Boolean(*b),
+                Key::Number(bits) => Object::Number(f64::from_bits(*bits)),
+                Key::String(s) => Object::String(Rc::clone(s)),
+            }
+        }
+    }
+
+    /// A hash map that remembers insertion order, so that printing a map and
+    /// iterating over its keys is deterministic.
+    #[derive(Debug, Default)]
+    pub struct Map {
+        entries: Vec<(Key, Object)>,
+        positions: HashMap<Key, usize>,
+    }
+
+    impl Map {
+        pub fn new() -> Self {
+            Map::default()
+        }
+
+        pub fn get(&self, key: &Key) -> Option<&Object> {
+            self.positions.get(key).map(|position| &self.entries[*position].1)
+        }
+
+        pub fn insert(&mut self, key: Key, value: Object) {
+            match self.positions.get(&key) {
+                Some(position) => self.entries[*position].1 = value,
+                None => {
+                    self.positions.insert(key.clone(), self.entries.len());
+                    self.entries.push((key, value));
+                }
+            }
+        }
+
+        pub fn remove(&mut self, key: &Key) -> Option<Object> {
+            let position = self.positions.remove(key)?;
+            let (_, value) = self.entries.remove(position);
+            for later in self.positions.values_mut() {
+                if *later > position {
+                    *later -= 1;
+                }
+            }
+            Some(value)
+        }
+
+        pub fn contains(&self, key: &Key) -> bool {
+            self.positions.contains_key(key)
+        }
+
+        pub fn len(&self) -> usize {
+            self.entries.len()
+        }
+
+        pub fn is_empty(&self) -> bool {
+            self.entries.is_empty()
+        }
+
+        pub fn iter(&self) -> impl Iterator<Item = &(Key, Object)> {
+            self.entries.iter()
+        }
+    }
+}
@@ -1,13 +1,19 @@
 pub mod object {
-    use crate::function::function::Function;
+    use std::{cell::RefCell, fmt, rc::Rc};
+
+    use crate::{class::class::{Class, Instance}, function::function::Function, gc::gc, map::map::Map};
 
     #[derive(Clone, Debug)]
     pub enum Object {
         Boolean(bool),
         Callable(Function),
+        Class(Rc<Class>),
+        Instance(Rc<RefCell<Instance>>),
+        List(Rc<RefCell<Vec<Object>>>),
+        Map(Rc<RefCell<Map>>),
         Null, 
         Number(f64),
-        String(String),
+        String(Rc<str>),
     }
 
     impl Object {
@@ -18,10 +24,78 @@ pub mod object {
                 (Object::Null, _) => false,
                 (Object::Boolean(left), Object::Boolean(right)) => left == right,
                 (Object::Number(left), Object::Number(right)) => left == right,
-                (Object::String(left), Object::String(right)) => left.eq(right),
+                (Object::String(left), Object::String(right)) => Rc::ptr_eq(left, right) || left == right,
+                (Object::Class(left), Object::Class(right)) => Rc::ptr_eq(left, right),
+                (Object::Instance(left), Object::Instance(right)) => Rc::ptr_eq(left, right),
+                (Object::List(left), Object::List(right)) => Rc::ptr_eq(left, right),
+                (Object::Map(left), Object::Map(right)) => Rc::ptr_eq(left, right),
                 _ => false, 
             }
         }
+
+        pub fn list(elements: Vec<Object>) -> Object {
+            Object::List(gc::track(RefCell::new(elements)))
+        }
+
+        pub fn map(map: Map) -> Object {
+            Object::Map(gc::track(RefCell::new(map)))
+        }
+
+        /// Writes the object, printing a collection that contains itself as
+        /// `[...]` or `{...}` rather than recursing forever.
+        fn write(&self, f: &mut fmt::Formatter<'_>, enclosing: &mut Vec<*const ()>) -> fmt::Result {
+            match self {
+                Object::Boolean(b) => write!(f, "{}", b),
+                Object::Callable(function) => write!(f, "{}", function),
+                Object::Class(class) => write!(f, "{}", class),
+                Object::Instance(instance) => write!(f, "{}", instance.borrow()),
+                Object::List(list) => {
+                    let pointer = Rc::as_ptr(list) as *const ();
+                    if enclosing.contains(&pointer) {
+                        return write!(f, "[...]");
+                    }
+
+                    enclosing.push(pointer);
+                    write!(f, "[")?;
+                    for (i, element) in list.borrow().iter().enumerate() {
+                        if i > 0 {
+                            write!(f, ", ")?;
+                        }
+                        element.write(f, enclosing)?;
+                    }
+                    enclosing.pop();
+                    write!(f, "]")
+                }
+                Object::Map(map) => {
+                    let pointer = Rc::as_ptr(map) as *const ();
+                    if enclosing.contains(&pointer) {
+                        return write!(f, "{{...}}");
+                    }
+
+                    enclosing.push(pointer);
+                    write!(f, "{{")?;
+                    for (i, (key, value)) in map.borrow().iter().enumerate() {
+                        if i > 0 {
+                            write!(f, ", ")?;
+                        }
+                        key.to_object().write(f, enclosing)?;
+                        write!(f, ": ")?;
+                        value.write(f, enclosing)?;
+                    }
+                    enclosing.pop();
+                    write!(f, "}}")
+                }
+                Object::Null => write!(f, "nil"),
+                Object::Number(n) => write!(f, "{}", n),
+                Object::String(s) => write!(f, "{}", s),
+            }
+        }
+    }
+
+    impl fmt::Display for Object {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            self.write(f, &mut Vec::new())
+        }
     }
 
 }
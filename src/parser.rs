@@ -1,14 +1,26 @@
 pub mod parser {
     use std::{convert, fmt, io};
 
-    use crate::{expr::expr::{Expr, LiteralValue}, object::object::Object, parser_error, stmt::stmt::Stmt, token::token::{Token, TokenType}};
+    use crate::{diagnostic::diagnostic::Diagnostic, expr::expr::{self, Expr, LiteralValue}, intern::intern::Interner, interpreter::interpreter::Frame, keywords::keywords, span::span::Span, stmt::stmt::Stmt, token::token::{Token, TokenType}};
 
     #[derive(Debug)]
     pub enum Error {
         Io(io::Error),
         Parse,
-        Runtime { token: Token, message: String },
-        Return { value: Object }
+        /// `trace` holds the calls that were running when the error
+        /// happened, outermost first. It is filled in as the error leaves
+        /// the innermost call.
+        Runtime { token: Token, message: String, trace: Vec<Frame> },
+    }
+
+    impl Error {
+        pub fn runtime(token: &Token, message: impl Into<String>) -> Self {
+            Error::Runtime {
+                token: token.clone(),
+                message: message.into(),
+                trace: Vec::new(),
+            }
+        }
     }
 
     impl fmt::Display for Error {
@@ -16,7 +28,6 @@ pub mod parser {
             match self {
                 Error::Io(underlying) => write!(f, "IoError {}", underlying),
                 Error::Parse => write!(f, "ParseError"),
-                Error::Return { value } => write!(f, "Return {:?}", value),
                 Error::Runtime { message, .. } => write!(f, "RuntimeError {}", message),
             }
         }
@@ -37,6 +48,9 @@ pub mod parser {
     pub struct Parser {
         tokens: Vec<Token>,
         current: usize,
+        pub diagnostics: Vec<Diagnostic>,
+        /// Equal string literals share one allocation.
+        strings: Interner,
     }
 
     macro_rules! matches {
@@ -54,20 +68,26 @@ pub mod parser {
 
     impl Parser {
         pub fn new(tokens: Vec<Token>) -> Self {
-            Parser {tokens, current: 0}
+            Parser { tokens, current: 0, diagnostics: Vec::new(), strings: Interner::new() }
         }
 
-        pub fn parse(&mut self) -> Result<Vec<Stmt>, Error> {
+        /// Parses the whole file. Statements with errors are left out and
+        /// parsing resumes after them, so the result is every statement that
+        /// parsed and `diagnostics` holds every error found.
+        pub fn parse(&mut self) -> Vec<Stmt> {
             let mut statements: Vec<Stmt> = Vec::new();
             while !self.is_at_end() {
-                statements.push(self.declaration()?);
+                statements.extend(self.declaration());
             }
 
-            Ok(statements)
+            statements
         }
 
-        fn declaration(&mut self) -> Result<Stmt, Error> {
-            let statement = if matches!(self, TokenType::Var) {
+        fn declaration(&mut self) -> Option<Stmt> {
+            let start = self.current;
+            let statement = if matches!(self, TokenType::Class) {
+                self.class_declaration()
+            } else if matches!(self, TokenType::Var) {
                 self.var_declaration()
             } else if matches!(self, TokenType::Fun) {
                 self.function("function".to_string())
@@ -76,16 +96,44 @@ pub mod parser {
             };
 
             match statement {
-                Err(Error::Parse) => {
+                Ok(statement) => Some(statement),
+                Err(_) => {
                     self.synchronize();
-                    Ok(Stmt::Nil)
+                    // Always make progress, or a token that cannot start a
+                    // statement would be tried again forever.
+                    if self.current == start {
+                        self.advance();
+                    }
+                    None
                 }
-                other => other,
             }
         }
 
 
+        fn class_declaration(&mut self) -> Result<Stmt, Error> {
+            let start = self.previous().span;
+            let name = self.consume(TokenType::Identifier, "Expect class name.".to_string())?;
+
+            let superclass = if matches!(self, TokenType::Less) {
+                let name = self.consume(TokenType::Identifier, "Expect superclass name.".to_string())?;
+                Some(Expr::Variable { id: expr::next_id(), span: name.span, name })
+            } else {
+                None
+            };
+
+            self.expect(TokenType::LeftBrace, "before class body")?;
+
+            let mut methods: Vec<Stmt> = Vec::new();
+            while !self.check(TokenType::RightBrace) && !self.is_at_end() {
+                methods.push(self.function("method".to_string())?);
+            }
+
+            self.expect(TokenType::RightBrace, "after class body")?;
+            Ok(Stmt::Class { name, superclass, methods, span: self.span_from(start) })
+        }
+
         fn var_declaration(&mut self) -> Result<Stmt, Error> {
+            let start = self.previous().span;
             let name = self.consume(TokenType::Identifier, "Expect variable name.".to_string())?;
 
             let initializer = if matches!(self, TokenType::Equal) {
@@ -94,24 +142,31 @@ pub mod parser {
                 None
             };
 
-            self.consume(TokenType::Semicolon, "Expect ';' after variable declaration.".to_string())?;
+            self.expect(TokenType::Semicolon, "after variable declaration")?;
 
-            Ok(Stmt::Var { name, initializer })
+            Ok(Stmt::Var { name, initializer, span: self.span_from(start) })
         }
 
         fn function(&mut self, kind: String) -> Result<Stmt, Error> {
+            // Methods have no keyword in front of their name.
+            let start = if self.previous().token_type == TokenType::Fun {
+                self.previous().span
+            } else {
+                self.peek().span
+            };
             let name = self.consume(
                 TokenType::Identifier,
                 format!("Expect {} name.", kind)
             )?;
 
-            self.consume(TokenType::LeftParen, format!("Expect '(' {} name.", kind))?;
+            self.expect(TokenType::LeftParen, &format!("after {} name", kind))?;
             let mut params: Vec<Token> = Vec::new();
 
             if !self.check(TokenType::RightParen) {
                 loop {
                     if params.len() >= 255 {
-                        self.error(self.peek(), "Cannot have more than 255 parameters.".to_string());
+                        let token = self.peek().clone();
+                        self.error(&token, "E104", "Cannot have more than 255 parameters.".to_string());
                     }
 
                     params.push(self.consume(TokenType::Identifier, "Expect parameter name.".to_string())?);
@@ -123,30 +178,35 @@ pub mod parser {
                 }
             }
 
-            self.consume(TokenType::RightParen, "Expect ')'  name.".to_string())?;
+            self.expect(TokenType::RightParen, "after parameters")?;
 
-            self.consume(
-                TokenType::LeftBrace,
-                format!("Expect '{{' before {} body.", kind)
-            )?;
+            self.expect(TokenType::LeftBrace, &format!("before {} body", kind))?;
 
             let body = self.block()?;
-            Ok(Stmt::Function { name, params, body })
+            Ok(Stmt::Function { name, params: params.into(), body: body.into(), span: self.span_from(start) })
         }
 
         fn block(&mut self) -> Result<Vec<Stmt>, Error> {
             let mut statements: Vec<Stmt> = Vec::new();
 
             while !self.check(TokenType::RightBrace) && !self.is_at_end() {
-               statements.push(self.declaration()?);
+                statements.extend(self.declaration());
             }
 
-            self.consume(TokenType::RightBrace, "Expect '}' after block.".to_string())?;
+            self.expect(TokenType::RightBrace, "after block")?;
             Ok(statements)
         }
 
         fn statement(&mut self) -> Result<Stmt, Error> {
-            if matches!(self, TokenType::For) {
+            if matches!(self, TokenType::Break) {
+                let keyword = self.previous().clone();
+                self.expect(TokenType::Semicolon, &format!("after {}", keywords::describe(&TokenType::Break)))?;
+                Ok(Stmt::Break { span: self.span_from(keyword.span), keyword })
+            } else if matches!(self, TokenType::Continue) {
+                let keyword = self.previous().clone();
+                self.expect(TokenType::Semicolon, &format!("after {}", keywords::describe(&TokenType::Continue)))?;
+                Ok(Stmt::Continue { span: self.span_from(keyword.span), keyword })
+            } else if matches!(self, TokenType::For) {
                 self.for_statement()
             } else if matches!(self, TokenType::If) {
                 self.if_statement()
@@ -157,14 +217,17 @@ pub mod parser {
             } else if matches!(self, TokenType::While) {
                 self.while_statement()
             } else if matches!(self, TokenType::LeftBrace) {
-                Ok(Stmt::Block { statements: self.block()? })
+                let start = self.previous().span;
+                let statements = self.block()?;
+                Ok(Stmt::Block { statements, span: self.span_from(start) })
             } else {
                 self.expression_statement()
             }
         }
 
         fn for_statement(&mut self) -> Result<Stmt, Error> {
-            self.consume(TokenType::LeftParen, "Expect '(' after 'for'.".to_string())?;
+            let start = self.previous().span;
+            self.expect(TokenType::LeftParen, &format!("after {}", keywords::describe(&TokenType::For)))?;
 
             let initializer = if matches!(self, TokenType::Semicolon) {
                 None
@@ -180,7 +243,7 @@ pub mod parser {
                 None
             };
 
-            self.consume(TokenType::Semicolon, "Expect ';' after loop condition".to_string())?;
+            self.expect(TokenType::Semicolon, "after loop condition")?;
 
             let increment = if !self.check(TokenType::RightParen) {
                 Some(self.expression()?)
@@ -188,33 +251,33 @@ pub mod parser {
                 None
             };
 
-            self.consume(TokenType::RightParen, "Expect ')' after for clauses.".to_string())?;
+            self.expect(TokenType::RightParen, "after for clauses")?;
 
-            let mut body = self.statement()?;
+            let body = self.statement()?;
+            let span = self.span_from(start);
 
-            if let Some(inc) = increment {
-                let inc_stmt = Stmt::Expression { expression: inc };
-                body = Stmt::Block { statements: vec![body, inc_stmt] }
-            }
-
-            body = Stmt::While {
+            let mut body = Stmt::While {
                 condition: condition.unwrap_or(Expr::Literal {
                     value: LiteralValue::Boolean(true),
+                    span: start,
                 }),
                 body: Box::new(body),
+                increment,
+                span,
             };
 
             if let Some(init_stmt) = initializer {
-                body = Stmt::Block { statements: vec![init_stmt, body] }
+                body = Stmt::Block { statements: vec![init_stmt, body], span }
             }
 
             Ok(body)
         }
 
         fn if_statement(&mut self) -> Result<Stmt, Error> {
-            self.consume(TokenType::LeftParen, "Expect '(' after 'if'. ".to_string())?;
+            let start = self.previous().span;
+            self.expect(TokenType::LeftParen, &format!("after {}", keywords::describe(&TokenType::If)))?;
             let condition = self.expression()?;
-            self.consume(TokenType::RightParen, "Expect ')' after if condition".to_string())?;
+            self.expect(TokenType::RightParen, "after if condition")?;
 
             let then_branch = Box::new(self.statement()?);
             let else_branch = if matches!(self, TokenType::Else) {
@@ -227,13 +290,15 @@ pub mod parser {
                 condition,
                 else_branch,
                 then_branch,
+                span: self.span_from(start),
             })
         }
 
         fn print_statement(&mut self) -> Result<Stmt, Error> {
+            let start = self.previous().span;
             let value = self.expression()?;
-            self.consume(TokenType::Semicolon, "Expect ';' after value.".to_string())?;
-            Ok(Stmt::Print { expression: value })
+            self.expect(TokenType::Semicolon, "after value")?;
+            Ok(Stmt::Print { expression: value, span: self.span_from(start) })
         }
 
         fn return_statement(&mut self) -> Result<Stmt, Error> {
@@ -244,43 +309,69 @@ pub mod parser {
                 None
             };
 
-            self.consume(TokenType::Semicolon, "Expect ';' after return values".to_string())?;
-            Ok(Stmt::Return { keyword, value })
+            self.expect(TokenType::Semicolon, "after return values")?;
+            Ok(Stmt::Return { span: self.span_from(keyword.span), keyword, value })
         }
 
         fn while_statement(&mut self) -> Result<Stmt, Error> {
-            self.consume(TokenType::LeftParen, "Expect '(' after 'while'.".to_string())?;
+            let start = self.previous().span;
+            self.expect(TokenType::LeftParen, &format!("after {}", keywords::describe(&TokenType::While)))?;
             let condition = self.expression()?;
-            self.consume(TokenType::RightParen, "Expect ')' after condition.".to_string())?;
+            self.expect(TokenType::RightParen, "after condition")?;
             let body = Box::new(self.statement()?);
 
-            Ok(Stmt::While { condition, body })
+            Ok(Stmt::While { condition, body, increment: None, span: self.span_from(start) })
         }
 
         fn expression_statement(&mut self) -> Result<Stmt, Error> {
+            let start = self.peek().span;
             let expr = self.expression()?;
-            self.consume(TokenType::Semicolon, "Expect ';' after expression.".to_string())?;
-            Ok(Stmt::Expression { expression: expr })
+            self.expect(TokenType::Semicolon, "after expression")?;
+            Ok(Stmt::Expression { expression: expr, span: self.span_from(start) })
         }
 
+        /// Skips to the next statement boundary after an error: just past a
+        /// `;` or a braced body, or before a keyword that starts a statement
+        /// or the `}` that closes the enclosing block. Braced bodies are
+        /// skipped whole, so nothing inside them is mistaken for a boundary.
         fn synchronize(&mut self) {
-            self.advance();
+            let mut depth = 0;
 
             while !self.is_at_end() {
-                if self.previous().token_type == TokenType::Semicolon {
-                    return;
+                if depth > 0 {
+                    match self.advance().token_type {
+                        TokenType::LeftBrace => depth += 1,
+                        TokenType::RightBrace if depth == 1 => return,
+                        TokenType::RightBrace => depth -= 1,
+                        _ => (),
+                    }
+                    continue;
                 }
 
                 match self.peek().token_type {
+                    TokenType::Semicolon => {
+                        self.advance();
+                        return;
+                    }
+                    TokenType::LeftBrace => {
+                        self.advance();
+                        depth = 1;
+                    }
+                    TokenType::RightBrace |
+                    TokenType::Break |
+                    TokenType::Continue |
+                    TokenType::Class |
                     TokenType::Fun |
                     TokenType::Var |
                     TokenType::For |
                     TokenType::If |
                     TokenType::While |
                     TokenType::Print |
-                    TokenType:: Return => return,
-                    _ => self.advance(),
-                };
+                    TokenType::Return => return,
+                    _ => {
+                        self.advance();
+                    }
+                }
             }
         }
 
@@ -294,17 +385,24 @@ pub mod parser {
 
             if matches!(self, TokenType::Equal) {
                 let value = Box::new(self.assignment()?);
+                let span = expr.span().to(value.span());
 
-                if let Expr::Variable {name} = expr {
-                    return Ok(Expr::Assign { name, value })
-                } 
+                match expr {
+                    Expr::Variable { name, .. } => return Ok(Expr::Assign { id: expr::next_id(), name, value, span }),
+                    Expr::Get { object, name, .. } => return Ok(Expr::Set { object, name, value, span }),
+                    Expr::Index { object, bracket, index, .. } => {
+                        return Ok(Expr::SetIndex { object, bracket, index, value, span })
+                    }
+                    _ => (),
+                }
 
-                let equals = self.previous();
-                self.error(equals, "Invalid assignment target.".to_string());
+                self.diagnostics.push(
+                    Diagnostic::error("E103", "Invalid assignment target.", expr.span())
+                        .with_label("this cannot be assigned to"),
+                );
             }
 
 
-
             Ok(expr)
         }
 
@@ -314,10 +412,11 @@ pub mod parser {
             while matches!(self, TokenType::Or) {
                 let operator: Token = (*self.previous()).clone();
                 let right: Expr = self.and_expr()?;
-                expr = Expr::Logical { 
+                expr = Expr::Logical {
+                    span: expr.span().to(right.span()),
                     left: Box::new(expr),
                     operator,
-                    right: Box::new(right) 
+                    right: Box::new(right),
                 }
             }
 
@@ -331,6 +430,7 @@ pub mod parser {
                 let operator: Token = (*self.previous()).clone();
                 let right: Expr = self.equality()?;
                 expr = Expr::Logical {
+                    span: expr.span().to(right.span()),
                     left: Box::new(expr),
                     operator,
                     right: Box::new(right),
@@ -346,10 +446,11 @@ pub mod parser {
             while matches!(self, TokenType::BangEqual, TokenType::EqualEqual) {
                 let operator: Token = (*self.previous()).clone();
                 let right: Expr = self.comparison()?;
-                expr = Expr::Binary { 
-                    left: Box::new(expr), 
+                expr = Expr::Binary {
+                    span: expr.span().to(right.span()),
+                    left: Box::new(expr),
                     operator,
-                    right:  Box::new(right)
+                    right: Box::new(right),
                 }
             }
 
@@ -368,10 +469,11 @@ pub mod parser {
             ) {
                 let operator: Token = self.previous().clone();
                 let right = self.addition()?;
-                expr = Expr::Binary { 
-                    left: Box::new(expr), 
-                    operator, 
-                    right: Box::new(right)
+                expr = Expr::Binary {
+                    span: expr.span().to(right.span()),
+                    left: Box::new(expr),
+                    operator,
+                    right: Box::new(right),
                 }
             }
 
@@ -384,8 +486,9 @@ pub mod parser {
             while matches!(self, TokenType::Minus, TokenType::Plus) {
                 let operator : Token = self.previous().clone();
                 let right = self.multiplication()?;
-                expr = Expr::Binary { 
-                    left: Box::new(expr), 
+                expr = Expr::Binary {
+                    span: expr.span().to(right.span()),
+                    left: Box::new(expr),
                     operator,
                     right: Box::new(right),
                 }
@@ -400,9 +503,10 @@ pub mod parser {
             while matches!(self, TokenType::Slash, TokenType::Star) {
                 let operator: Token = self.previous().clone();
                 let right = self.unary()?;
-                expr = Expr::Binary { 
+                expr = Expr::Binary {
+                    span: expr.span().to(right.span()),
                     left: Box::new(expr),
-                    operator, 
+                    operator,
                     right: Box::new(right),
                 }
             }
@@ -416,8 +520,9 @@ pub mod parser {
                 let operator: Token = self.previous().clone();
                 let right = self.unary()?;
                 Ok(Expr::Unary {
+                    span: operator.span.to(right.span()),
                     operator,
-                    right: Box::new(right)
+                    right: Box::new(right),
                 })
             } else {
                 self.call()
@@ -431,7 +536,25 @@ pub mod parser {
                 if matches!(self, TokenType::LeftParen) {
                     expr = self.finish_call(expr)?;
                 } else if matches!(self, TokenType::Dot) {
-                    let name = self.consume(TokenType::Identifier, "Expect property after '.'.".to_string())?;
+                    let name = self.consume(
+                        TokenType::Identifier,
+                        format!("Expect property name after {}.", keywords::describe(&TokenType::Dot))
+                    )?;
+                    expr = Expr::Get {
+                        span: expr.span().to(name.span),
+                        object: Box::new(expr),
+                        name,
+                    };
+                } else if matches!(self, TokenType::LeftBracket) {
+                    let bracket = self.previous().clone();
+                    let index = self.expression()?;
+                    let close = self.expect(TokenType::RightBracket, "after index")?;
+                    expr = Expr::Index {
+                        span: expr.span().to(close.span),
+                        object: Box::new(expr),
+                        bracket,
+                        index: Box::new(index),
+                    };
                 } else {
                     break;
                 }
@@ -447,69 +570,143 @@ pub mod parser {
             if !self.check(TokenType::RightParen) {
                 loop {
                     if arguments.len() >= 255 {
-                        self.error(self.peek(), "Cannot have more than 255 arguments".to_string());
+                        let token = self.peek().clone();
+                        self.error(&token, "E104", "Cannot have more than 255 arguments.".to_string());
                     }
 
                     arguments.push(self.expression()?);
-                    if matches!(self, TokenType::Comma) {
+                    if !matches!(self, TokenType::Comma) {
                         break;
                     }
                 }
             }
 
-            println!("test");
-
-            println!("{:?}", self.tokens.get(self.current).unwrap().token_type);
-            let parent = self.consume(TokenType::RightParen, "Expect ')' after arguments.".to_string())?;
+            let paren = self.expect(TokenType::RightParen, "after arguments")?;
             Ok(Expr::Call {
+                span: callee.span().to(paren.span),
                 callee: Box::new(callee),
-                paren: parent,
-                arguments
+                paren,
+                arguments,
             })
         }
 
         fn primary(&mut self) -> Result<Expr, Error> {
+            let span = self.peek().span;
             let expr = match &self.peek().token_type {
                 TokenType::False => Expr::Literal {
                     value: LiteralValue::Boolean(false),
+                    span,
                 },
                 TokenType::True => Expr::Literal {
-                    value: LiteralValue::Boolean(true), 
+                    value: LiteralValue::Boolean(true),
+                    span,
                 },
                 TokenType::Nil => Expr::Literal { 
-                    value:  LiteralValue::Null,
-                },
-                TokenType::String { literal } => Expr::Literal { 
-                    value: LiteralValue::String(literal.clone())
+                    value: LiteralValue::Null,
+                    span,
                 },
+                TokenType::String { literal } => {
+                    let literal = literal.clone();
+                    Expr::Literal {
+                        value: LiteralValue::String(self.strings.intern(&literal)),
+                        span,
+                    }
+                }
                 TokenType::Number { literal } => Expr::Literal { 
-                    value: LiteralValue::Number(literal.clone())
+                    value: LiteralValue::Number(*literal),
+                    span,
                 },
-                TokenType::Identifier => Expr::Variable { 
-                    name: self.peek().clone()
+                TokenType::Super => {
+                    let keyword = self.advance().clone();
+                    self.expect(TokenType::Dot, &format!("after {}", keywords::describe(&TokenType::Super)))?;
+                    let method = self.consume(
+                        TokenType::Identifier,
+                        "Expect superclass method name.".to_string()
+                    )?;
+                    return Ok(Expr::Super { id: expr::next_id(), span: self.span_from(span), keyword, method });
+                },
+                TokenType::This => Expr::This {
+                    id: expr::next_id(),
+                    keyword: self.peek().clone(),
+                    span,
+                },
+                TokenType::Identifier => Expr::Variable {
+                    id: expr::next_id(),
+                    name: self.peek().clone(),
+                    span,
+                },
+                TokenType::LeftBracket => {
+                    let bracket = self.advance().clone();
+                    let mut elements: Vec<Expr> = Vec::new();
+
+                    while !self.check(TokenType::RightBracket) && !self.is_at_end() {
+                        elements.push(self.expression()?);
+                        if !matches!(self, TokenType::Comma) {
+                            break;
+                        }
+                    }
+
+                    self.expect(TokenType::RightBracket, "after list elements")?;
+                    return Ok(Expr::List { bracket, elements, span: self.span_from(span) });
+                },
+                // A brace only starts a map where an expression is expected;
+                // at the start of a statement it opens a block.
+                TokenType::LeftBrace => {
+                    let brace = self.advance().clone();
+                    let mut entries: Vec<(Expr, Expr)> = Vec::new();
+
+                    while !self.check(TokenType::RightBrace) && !self.is_at_end() {
+                        let key = self.expression()?;
+                        self.expect(TokenType::Colon, "after map key")?;
+                        let value = self.expression()?;
+                        entries.push((key, value));
+                        if !matches!(self, TokenType::Comma) {
+                            break;
+                        }
+                    }
+
+                    self.expect(TokenType::RightBrace, "after map entries")?;
+                    return Ok(Expr::Map { brace, entries, span: self.span_from(span) });
                 },
                 TokenType::LeftParen => {
+                    self.advance();
                     let expr = self.expression()?;
-                    self.consume(TokenType::RightParen, "Expected ')' after expression.".to_string())?;
-                    Expr::Grouping { 
-                        expression: Box::new(expr)
-                    }
+                    self.expect(TokenType::RightParen, "after expression")?;
+                    return Ok(Expr::Grouping {
+                        expression: Box::new(expr),
+                        span: self.span_from(span),
+                    });
                 },
-                _ => return Err(self.error(self.peek(), "Expect expression.".to_string()))
+                _ => {
+                    let token = self.peek().clone();
+                    return Err(self.error(&token, "E102", "Expect expression.".to_string()));
+                }
             };
 
             self.advance();
             Ok(expr)
         }
 
+        /// The span from `start` to the end of the last consumed token.
+        fn span_from(&self, start: Span) -> Span {
+            start.to(self.previous().span)
+        }
+
         fn consume(&mut self, token_type: TokenType, message: String) -> Result<Token, Error> {
             if self.check(token_type) {
                 Ok(self.advance().clone())
             } else {
-                Err(self.error(self.peek(), message))
+                let token = self.peek().clone();
+                Err(self.error(&token, "E101", message))
             }
         }
 
+        /// Consumes a delimiter, naming both of its spellings if it is missing.
+        fn expect(&mut self, token_type: TokenType, context: &str) -> Result<Token, Error> {
+            let message = format!("Expect {} {}.", keywords::describe(&token_type), context);
+            self.consume(token_type, message)
+        }
+
         fn check(&self, token_type: TokenType) -> bool {
             if self.is_at_end() {
                 return false;
@@ -519,8 +716,14 @@ pub mod parser {
 
         }
 
-        fn error(&self, token: &Token, message: String) -> Error {
-            parser_error(token, message);
+        /// Records an error at `token`, naming what was found there.
+        fn error(&mut self, token: &Token, code: &'static str, message: String) -> Error {
+            let found = if token.token_type == TokenType::Eof {
+                "found end of file".to_string()
+            } else {
+                format!("found '{}'", token.lexeme)
+            };
+            self.diagnostics.push(Diagnostic::error(code, message, token.span).with_label(found));
             Error::Parse
         }
 
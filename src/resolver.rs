@@ -1,7 +1,7 @@
 pub mod resolver {
-    use std::{collections::HashMap, mem};
+    use std::{collections::HashMap, mem, rc::Rc};
 
-    use crate::{expr::expr::{self, Expr, LiteralValue}, interpreter::interpreter::Interpreter, parser::parser::Error, report, stmt::stmt::{self, Stmt}, token::token::{Token, TokenType}};
+    use crate::{diagnostic::diagnostic::Diagnostic, expr::expr::{self, Expr, ExprId, LiteralValue}, parser::parser::Error, stmt::stmt::{self, Stmt}, token::token::Token};
 
 
     #[derive(Debug, Clone)]
@@ -12,35 +12,56 @@ pub mod resolver {
         Method,
     }
 
-    pub struct Resolver<'i> {
-        interpreter: &'i mut Interpreter,
-        scopes: Vec<HashMap<String, bool>>,
+    #[derive(Debug, Clone)]
+    enum ClassType {
+        None,
+        Class,
+        Subclass,
+    }
+
+    /// A local declared in a scope: whether its initializer has finished,
+    /// and the slot it is stored in.
+    #[derive(Debug, Clone, Copy)]
+    struct Local {
+        defined: bool,
+        slot: usize,
+    }
+
+    pub struct Resolver {
+        /// How many scopes out each local variable expression refers to, and
+        /// its slot in that scope.
+        pub locals: HashMap<ExprId, (usize, usize)>,
+        scopes: Vec<HashMap<String, Local>>,
         current_function: FunctionType,
-        pub had_error: bool,
+        current_class: ClassType,
+        in_loop: bool,
+        pub diagnostics: Vec<Diagnostic>,
     }
 
-    impl<'i> Resolver<'i> {
-        pub fn new(interpreter: &'i mut Interpreter) -> Self {
+    impl Resolver {
+        pub fn new() -> Self {
             Resolver {
-                interpreter,
+                locals: HashMap::new(),
                 scopes: Vec::new(),
                 current_function: FunctionType::None,
-                had_error: false,
+                current_class: ClassType::None,
+                in_loop: false,
+                diagnostics: Vec::new(),
             }
         }
 
         fn resolve_stmt(&mut self, statement: &Stmt) {
-            statement.accept(self);
+            let _ = statement.accept(self);
         }
 
-        pub fn resolve_stmts(&mut self, statements: &Vec<Stmt>) {
+        pub fn resolve_stmts(&mut self, statements: &[Stmt]) {
             for statement in statements {
                 self.resolve_stmt(statement);
             }
         }
 
         fn resolve_expr(&mut self, expression: &Expr) {
-            expression.accept(self);
+            let _ = expression.accept(self);
         }
 
         fn begin_scope(&mut self) {
@@ -53,33 +74,27 @@ pub mod resolver {
 
         fn declare(&mut self, name: &Token) {
             let mut already_defined: bool = false;
-            match self.scopes.last_mut() {
-                Some(ref mut scope) => {
-                    already_defined = scope.contains_key(&name.lexeme);
-                    scope.insert(name.lexeme.clone(), false);
-                }
-                None => (),
-            };
+            if let Some(scope) = self.scopes.last_mut() {
+                already_defined = scope.contains_key(&*name.key());
+                let slot = scope.get(&*name.key()).map_or(scope.len(), |local| local.slot);
+                scope.insert(name.key().into_owned(), Local { defined: false, slot });
+            }
 
             if already_defined {
-                self.error(
-                    name,
-                    "Variable with this name already declared in this scope.",
-                );
+                self.error(name, "E201", "Variable with this name already declared in this scope.");
             }
         }
 
         fn define(&mut self, name: &Token) {
-            match self.scopes.last_mut() {
-                Some(ref mut scope) => {
-                    scope.insert(name.lexeme.clone(), true);
-                }
-                None => (),
-            };
+            if let Some(local) = self.scopes.last_mut().and_then(|scope| scope.get_mut(&*name.key())) {
+                local.defined = true;
+            }
         }
 
-        fn resolve_function(&mut self, params: &Vec<Token>, body: &Vec<Stmt>, tpe: FunctionType) {
+        fn resolve_function(&mut self, params: &[Token], body: &[Stmt], tpe: FunctionType) {
             let enclosing_function = mem::replace(&mut self.current_function, tpe);
+            // A function body is never inside the loop it is declared in.
+            let enclosing_loop = mem::replace(&mut self.in_loop, false);
 
             self.begin_scope();
             for param in params {
@@ -89,30 +104,41 @@ pub mod resolver {
             self.resolve_stmts(body);
             self.end_scope();
             self.current_function = enclosing_function;
+            self.in_loop = enclosing_loop;
+        }
+
+        /// Declares `this` or `super` as the only local of a new scope.
+        fn begin_implicit_scope(&mut self, name: &str) {
+            self.scopes.push(HashMap::from([(name.to_string(), Local { defined: true, slot: 0 })]));
         }
 
-        fn resolve_local(&mut self, name: &Token) {
-            for (i, scope) in self.scopes.iter().rev().enumerate() {
-                if scope.contains_key(&name.lexeme) {
-                    self.interpreter.resolve(name, i);
+        /// Records how many scopes out the innermost declaration of `name`
+        /// is, and its slot there. Names not found in any scope are left for
+        /// the globals.
+        fn resolve_local(&mut self, id: ExprId, name: &str) {
+            for (depth, scope) in self.scopes.iter().rev().enumerate() {
+                if let Some(local) = scope.get(name) {
+                    self.locals.insert(id, (depth, local.slot));
+                    return;
                 }
             }
         }
 
-        fn error(&mut self, token: &Token, message: &str) {
-            if token.token_type == TokenType::Eof {
-                report(token.line, " at end".to_string(), message.to_string());
-            } else {
-                report(token.line, format!(" at '{}'", token.lexeme).to_string(), message.to_string());
-            }
-            self.had_error = true;
+        fn error(&mut self, token: &Token, code: &'static str, message: &str) {
+            self.diagnostics.push(Diagnostic::error(code, message, token.span));
         }
     }
     
-    impl<'i> expr::Visitor<()> for Resolver<'i> {
-        fn visit_assign_expr(&mut self, name: &Token, value: &Expr) -> Result<(), Error> {
+    impl Default for Resolver {
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+
+    impl expr::Visitor<()> for Resolver {
+        fn visit_assign_expr(&mut self, id: ExprId, name: &Token, value: &Expr) -> Result<(), Error> {
             self.resolve_expr(value);
-            self.resolve_local(name);
+            self.resolve_local(id, &name.key());
             Ok(())
         }
 
@@ -131,7 +157,7 @@ pub mod resolver {
             &mut self,
             callee: &Expr,
             _paren: &Token,
-            arguments: &Vec<Expr>,
+            arguments: &[Expr],
         ) -> Result<(), Error> {
             self.resolve_expr(callee);
             for argument in arguments {
@@ -140,15 +166,41 @@ pub mod resolver {
             Ok(())
         }
 
+        fn visit_get_expr(&mut self, object: &Expr, _name: &Token) -> Result<(), Error> {
+            self.resolve_expr(object);
+            Ok(())
+        }
+
         fn visit_grouping_expr(&mut self, expression: &Expr) -> Result<(), Error> {
             self.resolve_expr(expression);
             Ok(())
         }
 
+        fn visit_index_expr(&mut self, object: &Expr, _bracket: &Token, index: &Expr) -> Result<(), Error> {
+            self.resolve_expr(object);
+            self.resolve_expr(index);
+            Ok(())
+        }
+
+        fn visit_list_expr(&mut self, _bracket: &Token, elements: &[Expr]) -> Result<(), Error> {
+            for element in elements {
+                self.resolve_expr(element);
+            }
+            Ok(())
+        }
+
         fn visit_literal_expr(&mut self, _value: &LiteralValue) -> Result<(), Error> {
             Ok(())
         }
 
+        fn visit_map_expr(&mut self, _brace: &Token, entries: &[(Expr, Expr)]) -> Result<(), Error> {
+            for (key, value) in entries {
+                self.resolve_expr(key);
+                self.resolve_expr(value);
+            }
+            Ok(())
+        }
+
         fn visit_logical_expr(
             &mut self,
             left: &Expr,
@@ -160,32 +212,138 @@ pub mod resolver {
             Ok(())
         }
 
+        fn visit_set_expr(&mut self, object: &Expr, _name: &Token, value: &Expr) -> Result<(), Error> {
+            self.resolve_expr(value);
+            self.resolve_expr(object);
+            Ok(())
+        }
+
+        fn visit_super_expr(&mut self, id: ExprId, keyword: &Token, _method: &Token) -> Result<(), Error> {
+            match self.current_class {
+                ClassType::None => {
+                    self.error(keyword, "E206", "Cannot use 'super' outside of a class.");
+                }
+                ClassType::Class => {
+                    self.diagnostics.push(
+                        Diagnostic::error("E207", "Cannot use 'super' in a class with no superclass.", keyword.span)
+                            .with_help("Name a superclass after '<' (◀️) in the class declaration."),
+                    );
+                }
+                ClassType::Subclass => self.resolve_local(id, "super"),
+            }
+            Ok(())
+        }
+
+        fn visit_set_index_expr(
+            &mut self,
+            object: &Expr,
+            _bracket: &Token,
+            index: &Expr,
+            value: &Expr,
+        ) -> Result<(), Error> {
+            self.resolve_expr(value);
+            self.resolve_expr(object);
+            self.resolve_expr(index);
+            Ok(())
+        }
+
+        fn visit_this_expr(&mut self, id: ExprId, keyword: &Token) -> Result<(), Error> {
+            if let ClassType::None = self.current_class {
+                self.error(keyword, "E205", "Cannot use 'this' outside of a class.");
+                return Ok(());
+            }
+
+            self.resolve_local(id, "this");
+            Ok(())
+        }
+
         fn visit_unary_expr(&mut self, _operator: &Token, right: &Expr) -> Result<(), Error> {
             self.resolve_expr(right);
             Ok(())
         }
 
-        fn visit_variable_expr(&mut self, name: &Token) -> Result<(), Error> {
+        fn visit_variable_expr(&mut self, id: ExprId, name: &Token) -> Result<(), Error> {
             if let Some(scope) = self.scopes.last() {
-                if let Some(flag) = scope.get(&name.lexeme) {
-                    if *flag == false {
-                        self.error(name, "Cannot read local variable in its own initializer.");
+                if let Some(local) = scope.get(&*name.key()) {
+                    if !local.defined {
+                        self.error(name, "E202", "Cannot read local variable in its own initializer.");
                     }
                 }
             };
-            self.resolve_local(name);
+            self.resolve_local(id, &name.key());
             Ok(())
         }
     }
 
-    impl<'i> stmt::Visitor<()> for Resolver<'i> {
-        fn visit_block_stmt(&mut self, statements: &Vec<Stmt>) -> Result<(), Error> {
+    impl stmt::Visitor<()> for Resolver {
+        fn visit_block_stmt(&mut self, statements: &[Stmt]) -> Result<(), Error> {
             self.begin_scope();
             self.resolve_stmts(statements);
             self.end_scope();
             Ok(())
         }
 
+        fn visit_break_stmt(&mut self, keyword: &Token) -> Result<(), Error> {
+            if !self.in_loop {
+                self.error(keyword, "E209", "Cannot use 'break' outside of a loop.");
+            }
+            Ok(())
+        }
+
+        fn visit_class_stmt(
+            &mut self,
+            name: &Token,
+            superclass: &Option<Expr>,
+            methods: &[Stmt],
+        ) -> Result<(), Error> {
+            let enclosing_class = mem::replace(&mut self.current_class, ClassType::Class);
+
+            self.declare(name);
+            self.define(name);
+
+            if let Some(superclass_expr) = superclass {
+                if let Expr::Variable { name: superclass_name, .. } = superclass_expr {
+                    if superclass_name.key() == name.key() {
+                        self.error(superclass_name, "E208", "A class cannot inherit from itself.");
+                    }
+                }
+
+                self.current_class = ClassType::Subclass;
+                self.resolve_expr(superclass_expr);
+
+                self.begin_implicit_scope("super");
+            }
+
+            self.begin_implicit_scope("this");
+
+            for method in methods {
+                if let Stmt::Function { name, params, body, .. } = method {
+                    let declaration = if name.key() == "init" {
+                        FunctionType::Initializer
+                    } else {
+                        FunctionType::Method
+                    };
+                    self.resolve_function(params, body, declaration);
+                }
+            }
+
+            self.end_scope();
+
+            if superclass.is_some() {
+                self.end_scope();
+            }
+
+            self.current_class = enclosing_class;
+            Ok(())
+        }
+
+        fn visit_continue_stmt(&mut self, keyword: &Token) -> Result<(), Error> {
+            if !self.in_loop {
+                self.error(keyword, "E210", "Cannot use 'continue' outside of a loop.");
+            }
+            Ok(())
+        }
+
         fn visit_expression_stmt(&mut self, expression: &Expr) -> Result<(), Error> {
             self.resolve_expr(expression);
             Ok(())
@@ -194,8 +352,8 @@ pub mod resolver {
         fn visit_function_stmt(
             &mut self,
             name: &Token,
-            params: &Vec<Token>,
-            body: &Vec<Stmt>,
+            params: &Rc<[Token]>,
+            body: &Rc<[Stmt]>,
         ) -> Result<(), Error> {
             self.declare(name);
             self.define(name);
@@ -225,12 +383,15 @@ pub mod resolver {
 
         fn visit_return_stmt(&mut self, keyword: &Token, value: &Option<Expr>) -> Result<(), Error> {
             if let FunctionType::None = self.current_function {
-                self.error(keyword, "Cannot return from top-level code.");
+                self.error(keyword, "E203", "Cannot return from top-level code.");
             }
 
             if let Some(return_value) = value {
                 if let FunctionType::Initializer = self.current_function {
-                    self.error(keyword, "Cannot return value from initializer.");
+                    self.diagnostics.push(
+                        Diagnostic::error("E204", "Cannot return value from initializer.", keyword.span)
+                            .with_note("Initializers always return the new instance."),
+                    );
                 }
                 self.resolve_expr(return_value);
             }
@@ -246,9 +407,14 @@ pub mod resolver {
             Ok(())
         }
 
-        fn visit_while_stmt(&mut self, condition: &Expr, body: &Stmt) -> Result<(), Error> {
+        fn visit_while_stmt(&mut self, condition: &Expr, body: &Stmt, increment: &Option<Expr>) -> Result<(), Error> {
             self.resolve_expr(condition);
+            let enclosing_loop = mem::replace(&mut self.in_loop, true);
             self.resolve_stmt(body);
+            self.in_loop = enclosing_loop;
+            if let Some(increment) = increment {
+                self.resolve_expr(increment);
+            }
             Ok(())
         }
     }
@@ -1,15 +1,21 @@
 pub mod scanner {
     use std::char;
-    use std::collections::HashMap;
 
-    use crate::{error, token::token::{Token, TokenType}};
+    use crate::{diagnostic::diagnostic::Diagnostic, keywords::keywords, span::span::Span, token::token::{Token, TokenType}, unicode::unicode};
 
+    /// Turns source text into tokens. `start` and `current` are byte offsets
+    /// into `source` and always sit on a char boundary, so multi-byte
+    /// characters such as emoji can be sliced out safely.
     pub struct Scanner {
         source: String,
         tokens: Vec<Token>,
         start: usize,
         current: usize,
         line: i32,
+        line_start: usize,
+        start_line: i32,
+        start_column: usize,
+        pub diagnostics: Vec<Diagnostic>,
     }
 
     impl Scanner {
@@ -20,16 +26,25 @@ pub mod scanner {
                 start: 0,
                 current: 0,
                 line: 1,
+                line_start: 0,
+                start_line: 1,
+                start_column: 1,
+                diagnostics: Vec::new(),
             }
         }
 
         pub fn scan_tokens(&mut self) -> &Vec<Token> {
             while !self.is_at_end() {
                 self.start = self.current;
+                self.start_line = self.line;
+                self.start_column = self.column(self.start);
                 self.scan_token();
             }
 
-            self.tokens.push(Token::new(TokenType::Eof, "".to_string(), self.line));
+            self.start = self.current;
+            self.start_line = self.line;
+            self.start_column = self.column(self.start);
+            self.tokens.push(Token::new(TokenType::Eof, "".to_string(), self.span()));
             &self.tokens
         }
 
@@ -41,6 +56,9 @@ pub mod scanner {
                  ')' => self.add_token(TokenType::RightParen),
                  '{' => self.add_token(TokenType::LeftBrace),
                  '}' => self.add_token(TokenType::RightBrace),
+                 '[' => self.add_token(TokenType::LeftBracket),
+                 ']' => self.add_token(TokenType::RightBracket),
+                 ':' => self.add_token(TokenType::Colon),
                  ',' => self.add_token(TokenType::Comma),
                  '.' => self.add_token(TokenType::Dot),
                  '-' => self.add_token(TokenType::Minus),
@@ -85,15 +103,32 @@ pub mod scanner {
                      }
                  }
                  ' ' | '\r' | '\t' => (),
-                 '\n' => self.line += 1,
+                 '\n' => self.new_line(),
                  '"' => self.string(),
                  character => {
-                     if character.is_digit(10) {
+                     let operator = keywords::emoji_operator_len(&self.source[self.start..]);
+
+                     if character.is_ascii_digit() {
                          self.number()
-                     } else if character.is_alphabetic() || character == '_' {
+                     } else if operator > 0 {
+                         self.current = self.start + operator;
+                         let token_type = keywords::lookup_operator(&self.source[self.start..self.current])
+                             .map(|operator| operator.token_type.clone())
+                             .expect("Emoji operator should be in the table.");
+                         self.add_token(token_type)
+                     } else if word_len(&self.source[self.start..]) > 0 {
                          self.identifier()
                      } else {
-                         error(self.line, "Unexpected character.".to_string())
+                         let diagnostic = Diagnostic::error(
+                             "E001",
+                             format!("Unexpected character '{}'.", character),
+                             self.span(),
+                         );
+                         self.diagnostics.push(if unicode::is_emoji_component(character) {
+                             diagnostic.with_help("Joiners, selectors and skin tones can only follow an emoji.")
+                         } else {
+                             diagnostic
+                         });
                      }
                  }
 
@@ -105,62 +140,45 @@ pub mod scanner {
                 return false;
             }
 
-            if self.source
-                    .chars()
-                    .nth(self.current)
-                    .expect("Unexpected end of source.") != expected {
+            if self.peek() != expected {
                 return false;
             }
 
-            self.current += 1;
+            self.current += expected.len_utf8();
             true
         }
 
         fn identifier(&mut self) {
-            let keywords : HashMap<&str, TokenType> = HashMap::from([
-                ("and", TokenType::And),
-                ("else", TokenType::Else),
-                ("false", TokenType::False),
-                ("for", TokenType::For),
-                ("fun", TokenType::Fun),
-                ("if", TokenType::If),
-                ("nil", TokenType::Nil),
-                ("print", TokenType::Print),
-                ("return", TokenType::Return),
-                ("true", TokenType::True),
-                ("var", TokenType::Var),
-                ("while", TokenType::While),
-            ]);
-
-            while self.peek().is_alphanumeric() || self.peek() == '_' {
-                self.advance();
-            }
+            self.current = self.start + word_len(&self.source[self.start..]);
 
             let text = self
                 .source
                 .get(self.start..self.current)
                 .expect("Unexpected end.");
 
-            let token_type: TokenType = keywords
-                .get(text)
-                .cloned()
-                .unwrap_or(TokenType::Identifier);
-
-            self.add_token(token_type)
+            match keywords::lookup(text) {
+                Some(keyword) => self.add_token(keyword.token_type.clone()),
+                None => self.add_token(TokenType::Identifier),
+            }
         }
 
         fn string(&mut self) {
             while self.peek() != '"' && !self.is_at_end() {
 
-                if self.peek() == '\n' {
-                    self.line += 1;
-                }
-
                 self.advance();
+
+                if self.previous() == '\n' {
+                    self.new_line();
+                }
             }
 
             if self.is_at_end() {
-                error(self.line, "Unterminated string.".to_string())
+                let quote = Span::new(self.start, self.start + 1, self.start_line, self.start_column);
+                self.diagnostics.push(
+                    Diagnostic::error("E002", "Unterminated string.", quote)
+                        .with_label("this string is never closed"),
+                );
+                return;
             }
 
             self.advance();
@@ -175,14 +193,14 @@ pub mod scanner {
         }
 
         fn number(&mut self) {
-            while self.peek().is_digit(10) {
+            while self.peek().is_ascii_digit() {
                 self.advance();
             }
 
-            if self.peek() == '.' && self.peek_next().is_digit(10) {
+            if self.peek() == '.' && self.peek_next().is_ascii_digit() {
                 self.advance();
 
-                while self.peek().is_digit(10) {
+                while self.peek().is_ascii_digit() {
                     self.advance();
                 }
             }
@@ -199,17 +217,39 @@ pub mod scanner {
 
 
         fn advance(&mut self) -> char {
-            self.current += 1;
-            let character_vector : Vec<char> = self.source.chars().collect();
-            character_vector[self.current - 1]
+            let character = self.peek();
+            self.current += character.len_utf8();
+            character
+        }
+
+        fn previous(&self) -> char {
+            self.source[..self.current].chars().next_back().unwrap_or('\0')
+        }
+
+        /// Called with `current` just past a newline.
+        fn new_line(&mut self) {
+            self.line += 1;
+            self.line_start = self.current;
+        }
+
+        fn column(&self, offset: usize) -> usize {
+            self.source[self.line_start..offset].chars().count() + 1
+        }
+
+        fn span(&self) -> Span {
+            Span::new(self.start, self.current, self.start_line, self.start_column)
+        }
+
+        fn remaining(&self) -> std::str::Chars<'_> {
+            self.source[self.current..].chars()
         }
 
         fn peek(&self) -> char {
-            self.source.chars().nth(self.current).unwrap_or('\0')
+            self.remaining().next().unwrap_or('\0')
         }
 
         fn peek_next(&self) -> char {
-            self.source.chars().nth(self.current + 1).unwrap_or('\0')
+            self.remaining().nth(1).unwrap_or('\0')
         }
 
         fn is_at_end(&self) -> bool {
@@ -218,10 +258,55 @@ pub mod scanner {
 
         fn add_token(&mut self, token_type: TokenType) {
             let text = self.source.get(self.start..self.current).expect("No token");
-            self.tokens.push(Token::new(token_type, text.to_string(), self.line))
+            self.tokens.push(Token::new(token_type, text.to_string(), self.span()))
+
+        }
+
+    }
 
+    /// Byte length of the identifier or keyword at the start of `text`, or 0
+    /// if none starts there.
+    ///
+    /// An identifier is a run of grapheme clusters, each of which is one of
+    ///
+    /// * a letter or `_`, followed by any combining marks,
+    /// * a digit or other number, except in first position,
+    /// * a whole emoji sequence (see `unicode::emoji_len`): a pictograph
+    ///   with its variation selector, skin tone and tags, ZWJ sequences such
+    ///   as 👩‍👩‍👧 and flags such as 🇯🇵.
+    ///
+    /// Emoji spellings of keywords and operators are reserved and always form
+    /// a token of their own, so `x🖨️` scans as `x` followed by `print`. Only
+    /// the exact sequence is reserved: `👍🏽` is an identifier, not `true`.
+    /// Keycaps such as 1️⃣ start with a digit and are not identifiers, and
+    /// joiners or selectors that do not follow an emoji are rejected.
+    pub fn word_len(text: &str) -> usize {
+        let mut length = 0;
+
+        while let Some(character) = text[length..].chars().next() {
+            let emoji = unicode::emoji_len(&text[length..]);
+
+            if emoji > 0 {
+                if keywords::is_reserved_emoji(&text[length..length + emoji]) {
+                    return if length == 0 { emoji } else { length };
+                }
+                length += emoji;
+            } else if is_alpha(character) || (length > 0 && is_continuation(character)) {
+                length += character.len_utf8();
+            } else {
+                break;
+            }
         }
 
+        length
+    }
+
+    pub fn is_alpha(character: char) -> bool {
+        character.is_alphabetic() || character == '_'
+    }
+
+    fn is_continuation(character: char) -> bool {
+        character.is_numeric() || unicode::is_combining_mark(character)
     }
 
 }
@@ -0,0 +1,24 @@
+pub mod span {
+    /// A range of source text. `start` and `end` are byte offsets into the
+    /// source, and `line` and `column` locate `start`, counting from 1.
+    /// Columns count chars, so an emoji made of several code points spans
+    /// several columns.
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+    pub struct Span {
+        pub start: usize,
+        pub end: usize,
+        pub line: i32,
+        pub column: usize,
+    }
+
+    impl Span {
+        pub fn new(start: usize, end: usize, line: i32, column: usize) -> Self {
+            Span { start, end, line, column }
+        }
+
+        /// The span from the start of `self` to the end of `other`.
+        pub fn to(self, other: Span) -> Span {
+            Span { end: other.end.max(self.start), ..self }
+        }
+    }
+}
@@ -0,0 +1,528 @@
+pub mod stdlib {
+    use std::rc::Rc;
+
+    use crate::{environment::environment::Environment, function::function::{Arity, Function, Runtime}, object::object::Object, parser::parser::Error};
+
+    type Native = fn(&mut dyn Runtime, &[Object]) -> Result<Object, Error>;
+
+    /// Defines every native function in `globals`. Each module below can
+    /// also be defined on its own.
+    pub fn define(globals: &mut Environment) {
+        time::define(globals);
+        math::define(globals);
+        string::define(globals);
+        types::define(globals);
+        conversion::define(globals);
+        list::define(globals);
+        map::define(globals);
+        memory::define(globals);
+    }
+
+    fn register(globals: &mut Environment, natives: &[(&str, Arity, Native)]) {
+        for &(name, arity, body) in natives {
+            globals.define(
+                name.to_string(),
+                Object::Callable(Function::Native { name: name.to_string(), arity, body: Rc::new(body) }),
+            );
+        }
+    }
+
+    pub mod time {
+        use std::time::{SystemTime, UNIX_EPOCH};
+
+        use crate::{environment::environment::Environment, function::function::{Arity::Exact, Runtime}, object::object::Object, parser::parser::Error};
+
+        use super::register;
+
+        pub fn define(globals: &mut Environment) {
+            register(globals, &[("clock", Exact(0), clock)]);
+        }
+
+        /// Seconds since the Unix epoch.
+        fn clock(interpreter: &mut dyn Runtime, _arguments: &[Object]) -> Result<Object, Error> {
+            let elapsed = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_err(|error| interpreter.error(error.to_string()))?;
+            Ok(Object::Number(elapsed.as_secs_f64()))
+        }
+    }
+
+    pub mod math {
+        use std::{cell::Cell, time::{SystemTime, UNIX_EPOCH}};
+
+        use crate::{environment::environment::Environment, function::function::{Arity::{AtLeast, Exact}, Runtime}, object::object::Object, parser::parser::Error};
+
+        use super::register;
+
+        thread_local! {
+            static STATE: Cell<u64> = Cell::new(seed_from_clock());
+        }
+
+        pub fn define(globals: &mut Environment) {
+            register(globals, &[
+                ("sqrt", Exact(1), sqrt),
+                ("floor", Exact(1), floor),
+                ("pow", Exact(2), pow),
+                ("random", Exact(0), random),
+                ("seed", Exact(1), seed),
+                ("max", AtLeast(1), max),
+                ("min", AtLeast(1), min),
+            ]);
+        }
+
+        fn number(interpreter: &dyn Runtime, object: &Object, what: &str) -> Result<f64, Error> {
+            match object {
+                Object::Number(n) => Ok(*n),
+                _ => Err(interpreter.error(format!("Can only take the {} of a number.", what))),
+            }
+        }
+
+        fn sqrt(interpreter: &mut dyn Runtime, arguments: &[Object]) -> Result<Object, Error> {
+            Ok(Object::Number(number(interpreter, &arguments[0], "square root")?.sqrt()))
+        }
+
+        fn floor(interpreter: &mut dyn Runtime, arguments: &[Object]) -> Result<Object, Error> {
+            Ok(Object::Number(number(interpreter, &arguments[0], "floor")?.floor()))
+        }
+
+        fn pow(interpreter: &mut dyn Runtime, arguments: &[Object]) -> Result<Object, Error> {
+            match (&arguments[0], &arguments[1]) {
+                (Object::Number(base), Object::Number(exponent)) => Ok(Object::Number(base.powf(*exponent))),
+                _ => Err(interpreter.error("Can only raise a number to a number.")),
+            }
+        }
+
+        fn max(interpreter: &mut dyn Runtime, arguments: &[Object]) -> Result<Object, Error> {
+            extreme(interpreter, arguments, "max", f64::max)
+        }
+
+        fn min(interpreter: &mut dyn Runtime, arguments: &[Object]) -> Result<Object, Error> {
+            extreme(interpreter, arguments, "min", f64::min)
+        }
+
+        fn extreme(interpreter: &dyn Runtime, arguments: &[Object], what: &str, pick: fn(f64, f64) -> f64) -> Result<Object, Error> {
+            let mut result = number(interpreter, &arguments[0], what)?;
+            for argument in &arguments[1..] {
+                result = pick(result, number(interpreter, argument, what)?);
+            }
+            Ok(Object::Number(result))
+        }
+
+        /// A number in `[0, 1)` from a xorshift generator. The sequence is
+        /// seeded from the clock unless `seed` has been called.
+        fn random(_interpreter: &mut dyn Runtime, _arguments: &[Object]) -> Result<Object, Error> {
+            let next = STATE.with(|state| {
+                let mut x = state.get();
+                x ^= x << 13;
+                x ^= x >> 7;
+                x ^= x << 17;
+                state.set(x);
+                x
+            });
+            Ok(Object::Number((next >> 11) as f64 / (1u64 << 53) as f64))
+        }
+
+        fn seed(interpreter: &mut dyn Runtime, arguments: &[Object]) -> Result<Object, Error> {
+            match &arguments[0] {
+                Object::Number(n) if n.fract() == 0.0 => {
+                    STATE.with(|state| state.set(scramble(*n as i64 as u64)));
+                    Ok(Object::Null)
+                }
+                _ => Err(interpreter.error("Seed must be an integer.")),
+            }
+        }
+
+        fn seed_from_clock() -> u64 {
+            let nanos = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|elapsed| elapsed.as_nanos() as u64)
+                .unwrap_or(0);
+            scramble(nanos)
+        }
+
+        /// Spreads the bits of a seed so that small seeds give unrelated
+        /// sequences. Xorshift must never start from zero.
+        fn scramble(seed: u64) -> u64 {
+            let mut z = seed.wrapping_add(0x9E37_79B9_7F4A_7C15);
+            z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+            match z ^ (z >> 31) {
+                0 => 1,
+                z => z,
+            }
+        }
+    }
+
+    pub mod string {
+        use crate::{environment::environment::Environment, function::function::{Arity::Exact, Runtime}, object::object::Object, parser::parser::Error};
+
+        use super::{list::position, register};
+
+        pub fn define(globals: &mut Environment) {
+            register(globals, &[
+                ("len", Exact(1), len),
+                ("upper", Exact(1), upper),
+                ("split", Exact(2), split),
+                ("substr", Exact(3), substr),
+                ("index_of", Exact(2), index_of),
+            ]);
+        }
+
+        /// The number of characters in a string, or of elements in a list
+        /// or map.
+        fn len(interpreter: &mut dyn Runtime, arguments: &[Object]) -> Result<Object, Error> {
+            match &arguments[0] {
+                Object::String(s) => Ok(Object::Number(s.chars().count() as f64)),
+                Object::List(list) => Ok(Object::Number(list.borrow().len() as f64)),
+                Object::Map(map) => Ok(Object::Number(map.borrow().len() as f64)),
+                _ => Err(interpreter.error("Can only take the length of a string, list or map.")),
+            }
+        }
+
+        fn upper(interpreter: &mut dyn Runtime, arguments: &[Object]) -> Result<Object, Error> {
+            match &arguments[0] {
+                Object::String(s) => Ok(Object::String(s.to_uppercase().into())),
+                _ => Err(interpreter.error("Can only uppercase a string.")),
+            }
+        }
+
+        /// Splits on every occurrence of a separator. An empty separator
+        /// splits a string into its characters.
+        fn split(interpreter: &mut dyn Runtime, arguments: &[Object]) -> Result<Object, Error> {
+            match (&arguments[0], &arguments[1]) {
+                (Object::String(s), Object::String(separator)) => {
+                    let parts: Vec<Object> = if separator.is_empty() {
+                        s.chars().map(|c| Object::String(c.to_string().into())).collect()
+                    } else {
+                        s.split(&**separator).map(|part| Object::String(part.into())).collect()
+                    };
+                    Ok(Object::list(parts))
+                }
+                _ => Err(interpreter.error("Can only split a string by a string.")),
+            }
+        }
+
+        /// The characters from `start` up to but not including `end`,
+        /// counted and clamped the way `slice` does for lists.
+        fn substr(interpreter: &mut dyn Runtime, arguments: &[Object]) -> Result<Object, Error> {
+            match &arguments[0] {
+                Object::String(s) => {
+                    let length = s.chars().count();
+                    let clamp = |index: &Object| -> Result<usize, Error> {
+                        let position = position(length, index).map_err(|message| interpreter.error(message))?;
+                        Ok(position.clamp(0, length as i64) as usize)
+                    };
+                    let start = clamp(&arguments[1])?;
+                    let end = clamp(&arguments[2])?.max(start);
+                    Ok(Object::String(s.chars().skip(start).take(end - start).collect::<String>().into()))
+                }
+                _ => Err(interpreter.error("Can only take a substring of a string.")),
+            }
+        }
+
+        /// The character index of the first occurrence of `needle`, or -1.
+        fn index_of(interpreter: &mut dyn Runtime, arguments: &[Object]) -> Result<Object, Error> {
+            match (&arguments[0], &arguments[1]) {
+                (Object::String(s), Object::String(needle)) => Ok(Object::Number(match s.find(&**needle) {
+                    Some(offset) => s[..offset].chars().count() as f64,
+                    None => -1.0,
+                })),
+                _ => Err(interpreter.error("Can only search a string for a string.")),
+            }
+        }
+    }
+
+    pub mod types {
+        use crate::{environment::environment::Environment, function::function::{Arity::Exact, Runtime}, object::object::Object, parser::parser::Error};
+
+        use super::register;
+
+        pub fn define(globals: &mut Environment) {
+            register(globals, &[("type_of", Exact(1), type_of)]);
+        }
+
+        fn type_of(_interpreter: &mut dyn Runtime, arguments: &[Object]) -> Result<Object, Error> {
+            let name = match &arguments[0] {
+                Object::Boolean(_) => "boolean",
+                Object::Callable(_) => "function",
+                Object::Class(_) => "class",
+                Object::Instance(_) => "instance",
+                Object::List(_) => "list",
+                Object::Map(_) => "map",
+                Object::Null => "nil",
+                Object::Number(_) => "number",
+                Object::String(_) => "string",
+            };
+            Ok(Object::String(name.into()))
+        }
+    }
+
+    pub mod conversion {
+        use crate::{environment::environment::Environment, function::function::{Arity::Exact, Runtime}, object::object::Object, parser::parser::Error};
+
+        use super::register;
+
+        pub fn define(globals: &mut Environment) {
+            register(globals, &[
+                ("str", Exact(1), str),
+                ("num", Exact(1), num),
+            ]);
+        }
+
+        /// Converts any value to the string `print` would show.
+        fn str(_interpreter: &mut dyn Runtime, arguments: &[Object]) -> Result<Object, Error> {
+            Ok(Object::String(arguments[0].to_string().into()))
+        }
+
+        /// Parses a string as a number. Numbers pass through unchanged.
+        fn num(interpreter: &mut dyn Runtime, arguments: &[Object]) -> Result<Object, Error> {
+            match &arguments[0] {
+                Object::Number(n) => Ok(Object::Number(*n)),
+                Object::String(s) => s
+                    .trim()
+                    .parse::<f64>()
+                    .ok()
+                    .filter(|n| n.is_finite())
+                    .map(Object::Number)
+                    .ok_or_else(|| interpreter.error(format!("Cannot convert '{}' to a number.", s))),
+                _ => Err(interpreter.error("Can only convert strings to numbers.")),
+            }
+        }
+    }
+
+    pub mod list {
+        use std::{cmp::Ordering, slice};
+
+        use crate::{environment::environment::Environment, function::function::{Arity::Exact, Runtime}, object::object::Object, parser::parser::Error};
+
+        use super::register;
+
+        pub fn define(globals: &mut Environment) {
+            register(globals, &[
+                ("push", Exact(2), push),
+                ("pop", Exact(1), pop),
+                ("insert", Exact(3), insert),
+                ("slice", Exact(3), slice),
+                ("sort", Exact(1), sort),
+                ("map", Exact(2), map),
+                ("filter", Exact(2), filter),
+            ]);
+        }
+
+        /// Turns a possibly negative index into a position in a list of
+        /// `length` elements, counting negative indices from the end.
+        pub fn resolve_index(length: usize, index: &Object) -> Result<usize, String> {
+            let position = position(length, index)?;
+            if position < 0 || position >= length as i64 {
+                Err(format!("List index {} out of range for length {}.", index, length))
+            } else {
+                Ok(position as usize)
+            }
+        }
+
+        pub(super) fn position(length: usize, index: &Object) -> Result<i64, String> {
+            match index {
+                Object::Number(n) if n.fract() == 0.0 => {
+                    let n = *n as i64;
+                    Ok(if n < 0 { n + length as i64 } else { n })
+                }
+                _ => Err("List index must be an integer.".to_string()),
+            }
+        }
+
+        fn push(interpreter: &mut dyn Runtime, arguments: &[Object]) -> Result<Object, Error> {
+            match &arguments[0] {
+                Object::List(list) => {
+                    list.borrow_mut().push(arguments[1].clone());
+                    Ok(Object::Null)
+                }
+                _ => Err(interpreter.error("Can only push onto a list.")),
+            }
+        }
+
+        fn pop(interpreter: &mut dyn Runtime, arguments: &[Object]) -> Result<Object, Error> {
+            match &arguments[0] {
+                Object::List(list) => list
+                    .borrow_mut()
+                    .pop()
+                    .ok_or_else(|| interpreter.error("Cannot pop from an empty list.")),
+                _ => Err(interpreter.error("Can only pop from a list.")),
+            }
+        }
+
+        fn insert(interpreter: &mut dyn Runtime, arguments: &[Object]) -> Result<Object, Error> {
+            match &arguments[0] {
+                Object::List(list) => {
+                    let length = list.borrow().len();
+                    let position = position(length, &arguments[1]).map_err(|message| interpreter.error(message))?;
+                    if position < 0 || position > length as i64 {
+                        return Err(interpreter.error(format!(
+                            "Insert index {} out of range for length {}.",
+                            arguments[1], length
+                        )));
+                    }
+                    list.borrow_mut().insert(position as usize, arguments[2].clone());
+                    Ok(Object::Null)
+                }
+                _ => Err(interpreter.error("Can only insert into a list.")),
+            }
+        }
+
+        /// Copies the elements from `start` up to but not including `end`.
+        /// Negative bounds count from the end and bounds past either end are
+        /// clamped, so slicing never fails on a valid list.
+        fn slice(interpreter: &mut dyn Runtime, arguments: &[Object]) -> Result<Object, Error> {
+            match &arguments[0] {
+                Object::List(list) => {
+                    let elements = list.borrow();
+                    let clamp = |index: &Object| -> Result<usize, Error> {
+                        let position = position(elements.len(), index).map_err(|message| interpreter.error(message))?;
+                        Ok(position.clamp(0, elements.len() as i64) as usize)
+                    };
+                    let start = clamp(&arguments[1])?;
+                    let end = clamp(&arguments[2])?.max(start);
+                    Ok(Object::list(elements[start..end].to_vec()))
+                }
+                _ => Err(interpreter.error("Can only slice a list.")),
+            }
+        }
+
+        /// Sorts a list of numbers or a list of strings in place.
+        fn sort(interpreter: &mut dyn Runtime, arguments: &[Object]) -> Result<Object, Error> {
+            match &arguments[0] {
+                Object::List(list) => {
+                    let mut elements = list.borrow_mut();
+                    let all_numbers = elements.iter().all(|e| matches!(e, Object::Number(_)));
+                    let all_strings = elements.iter().all(|e| matches!(e, Object::String(_)));
+                    if !all_numbers && !all_strings {
+                        return Err(interpreter.error("Can only sort a list of numbers or a list of strings."));
+                    }
+
+                    elements.sort_by(|left, right| match (left, right) {
+                        (Object::Number(l), Object::Number(r)) => l.partial_cmp(r).unwrap_or(Ordering::Equal),
+                        (Object::String(l), Object::String(r)) => l.cmp(r),
+                        _ => Ordering::Equal,
+                    });
+                    Ok(Object::Null)
+                }
+                _ => Err(interpreter.error("Can only sort a list.")),
+            }
+        }
+
+        /// A new list of `function` applied to each element.
+        fn map(interpreter: &mut dyn Runtime, arguments: &[Object]) -> Result<Object, Error> {
+            let elements = elements(interpreter, &arguments[0], "Can only map over a list.")?;
+            let paren = interpreter.call_site();
+            let mut mapped = Vec::with_capacity(elements.len());
+            for element in elements {
+                mapped.push(interpreter.call(&arguments[1], &[element], &paren)?);
+            }
+            Ok(Object::list(mapped))
+        }
+
+        /// A new list of the elements for which `predicate` is truthy.
+        fn filter(interpreter: &mut dyn Runtime, arguments: &[Object]) -> Result<Object, Error> {
+            let elements = elements(interpreter, &arguments[0], "Can only filter a list.")?;
+            let paren = interpreter.call_site();
+            let mut kept = Vec::new();
+            for element in elements {
+                let keep = interpreter.call(&arguments[1], slice::from_ref(&element), &paren)?;
+                if !matches!(keep, Object::Null | Object::Boolean(false)) {
+                    kept.push(element);
+                }
+            }
+            Ok(Object::list(kept))
+        }
+
+        /// Copies the elements out first, so that callbacks are free to
+        /// change the list while it is walked.
+        fn elements(interpreter: &dyn Runtime, list: &Object, message: &str) -> Result<Vec<Object>, Error> {
+            match list {
+                Object::List(list) => Ok(list.borrow().clone()),
+                _ => Err(interpreter.error(message)),
+            }
+        }
+    }
+
+    pub mod map {
+        use crate::{environment::environment::Environment, function::function::{Arity::Exact, Runtime}, map::map::Key, object::object::Object, parser::parser::Error};
+
+        use super::register;
+
+        pub fn define(globals: &mut Environment) {
+            register(globals, &[
+                ("keys", Exact(1), keys),
+                ("values", Exact(1), values),
+                ("has", Exact(2), has),
+                ("remove", Exact(2), remove),
+            ]);
+        }
+
+        /// The keys of a map as a new list, in insertion order.
+        fn keys(interpreter: &mut dyn Runtime, arguments: &[Object]) -> Result<Object, Error> {
+            match &arguments[0] {
+                Object::Map(map) => Ok(Object::list(map.borrow().iter().map(|(key, _)| key.to_object()).collect())),
+                _ => Err(interpreter.error("Can only take the keys of a map.")),
+            }
+        }
+
+        /// The values of a map as a new list, in insertion order.
+        fn values(interpreter: &mut dyn Runtime, arguments: &[Object]) -> Result<Object, Error> {
+            match &arguments[0] {
+                Object::Map(map) => Ok(Object::list(map.borrow().iter().map(|(_, value)| value.clone()).collect())),
+                _ => Err(interpreter.error("Can only take the values of a map.")),
+            }
+        }
+
+        fn has(interpreter: &mut dyn Runtime, arguments: &[Object]) -> Result<Object, Error> {
+            match &arguments[0] {
+                Object::Map(map) => {
+                    let key = Key::from_object(&arguments[1]).map_err(|message| interpreter.error(message))?;
+                    Ok(Object::Boolean(map.borrow().contains(&key)))
+                }
+                _ => Err(interpreter.error("Can only look up keys in a map.")),
+            }
+        }
+
+        /// Removes a key and returns its value, or nil if it was not there.
+        fn remove(interpreter: &mut dyn Runtime, arguments: &[Object]) -> Result<Object, Error> {
+            match &arguments[0] {
+                Object::Map(map) => {
+                    let key = Key::from_object(&arguments[1]).map_err(|message| interpreter.error(message))?;
+                    Ok(map.borrow_mut().remove(&key).unwrap_or(Object::Null))
+                }
+                _ => Err(interpreter.error("Can only remove keys from a map.")),
+            }
+        }
+    }
+
+    pub mod memory {
+        use crate::{environment::environment::Environment, function::function::{Arity::Exact, Runtime}, gc::gc, map::map::{Key, Map}, object::object::Object, parser::parser::Error};
+
+        use super::register;
+
+        pub fn define(globals: &mut Environment) {
+            register(globals, &[
+                ("gc", Exact(0), collect),
+                ("gc_stats", Exact(0), stats),
+            ]);
+        }
+
+        /// Frees everything only kept alive by reference cycles and returns
+        /// how many objects that was.
+        fn collect(_interpreter: &mut dyn Runtime, _arguments: &[Object]) -> Result<Object, Error> {
+            Ok(Object::Number(gc::collect() as f64))
+        }
+
+        /// A map of the heap objects alive now and of the collections run
+        /// and objects freed so far.
+        fn stats(_interpreter: &mut dyn Runtime, _arguments: &[Object]) -> Result<Object, Error> {
+            let stats = gc::stats();
+            let mut map = Map::new();
+            for (name, value) in [("live", stats.live), ("collections", stats.collections), ("freed", stats.freed)] {
+                map.insert(Key::String(name.into()), Object::Number(value as f64));
+            }
+            Ok(Object::map(map))
+        }
+    }
+}
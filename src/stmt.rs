@@ -1,66 +1,123 @@
 pub mod stmt {
-    use crate::{expr::expr::Expr, parser::parser::Error, token::token::Token};
+    use std::rc::Rc;
+
+    use crate::{expr::expr::Expr, parser::parser::Error, span::span::Span, token::token::Token};
 
     #[derive(Clone, Debug)]
     pub enum Stmt {
-        Block { statements: Vec<Stmt> },
-        Expression { expression: Expr },
-        Function { 
+        Block {
+            statements: Vec<Stmt>,
+            span: Span,
+        },
+        Break {
+            keyword: Token,
+            span: Span,
+        },
+        Class {
+            name: Token,
+            superclass: Option<Expr>,
+            methods: Vec<Stmt>,
+            span: Span,
+        },
+        Continue {
+            keyword: Token,
+            span: Span,
+        },
+        Expression {
+            expression: Expr,
+            span: Span,
+        },
+        /// The parameters and body are shared with every function value made
+        /// from the declaration.
+        Function {
             name: Token,
-            params: Vec<Token>,
-            body: Vec<Stmt>
+            params: Rc<[Token]>,
+            body: Rc<[Stmt]>,
+            span: Span,
         },
-        If { 
+        If {
             condition: Expr,
             else_branch: Box<Option<Stmt>>,
-            then_branch: Box<Stmt>
+            then_branch: Box<Stmt>,
+            span: Span,
         },
-        Print { 
+        Print {
             expression: Expr,
+            span: Span,
         },
         Return {
             keyword: Token,
             value: Option<Expr>,
+            span: Span,
         },
-        Var { 
+        Var {
             name: Token,
             initializer: Option<Expr>,
+            span: Span,
         },
-        While { 
+        /// `increment` is the last clause of a `for` loop, run after every
+        /// pass through the body, including ones left by `continue`.
+        While {
             condition: Expr,
-            body: Box<Stmt>
+            body: Box<Stmt>,
+            increment: Option<Expr>,
+            span: Span,
         },
-        Nil,
     }
 
 
     pub trait Visitor<R> {
-        fn visit_block_stmt(&mut self, statements: &Vec<Stmt>) -> Result<R, Error>;
+        fn visit_block_stmt(&mut self, statements: &[Stmt]) -> Result<R, Error>;
+        fn visit_break_stmt(&mut self, keyword: &Token) -> Result<R, Error>;
+        fn visit_class_stmt(&mut self, name: &Token, superclass: &Option<Expr>, methods: &[Stmt]) -> Result<R, Error>;
+        fn visit_continue_stmt(&mut self, keyword: &Token) -> Result<R, Error>;
         fn visit_expression_stmt(&mut self, expression: &Expr) -> Result<R, Error>;
-        fn visit_function_stmt(&mut self, name: &Token, params: &Vec<Token>, body: &Vec<Stmt>) -> Result<R, Error>;
+        fn visit_function_stmt(&mut self, name: &Token, params: &Rc<[Token]>, body: &Rc<[Stmt]>) -> Result<R, Error>;
         fn visit_if_stmt(&mut self, condition: &Expr, else_branch: &Option<Stmt>, then_branch: &Stmt) -> Result<R, Error>;
         fn visit_print_stmt(&mut self, expression: &Expr) -> Result<R, Error>;
         fn visit_return_stmt(&mut self, keyword: &Token, value: &Option<Expr>) -> Result<R, Error>;
         fn visit_var_stmt(&mut self, name: &Token, initializer: &Option<Expr>) -> Result<R, Error>;
-        fn visit_while_stmt(&mut self, condition: &Expr, body: &Stmt) -> Result<R, Error>;
+        fn visit_while_stmt(&mut self, condition: &Expr, body: &Stmt, increment: &Option<Expr>) -> Result<R, Error>;
     }
 
     impl Stmt {
+        pub fn span(&self) -> Span {
+            match self {
+                Stmt::Block { span, .. }
+                | Stmt::Break { span, .. }
+                | Stmt::Class { span, .. }
+                | Stmt::Continue { span, .. }
+                | Stmt::Expression { span, .. }
+                | Stmt::Function { span, .. }
+                | Stmt::If { span, .. }
+                | Stmt::Print { span, .. }
+                | Stmt::Return { span, .. }
+                | Stmt::Var { span, .. }
+                | Stmt::While { span, .. } => *span,
+            }
+        }
+
         pub fn accept<R>(&self, visitor: &mut dyn Visitor<R>) -> Result<R, Error> {
             match self {
-                Stmt::Block { statements } => visitor.visit_block_stmt(statements),
-                Stmt::Expression { expression } => visitor.visit_expression_stmt(expression),
-                Stmt::Function { name, params, body } => { 
+                Stmt::Block { statements, .. } => visitor.visit_block_stmt(statements),
+                Stmt::Break { keyword, .. } => visitor.visit_break_stmt(keyword),
+                Stmt::Class { name, superclass, methods, .. } => {
+                    visitor.visit_class_stmt(name, superclass, methods)
+                },
+                Stmt::Continue { keyword, .. } => visitor.visit_continue_stmt(keyword),
+                Stmt::Expression { expression, .. } => visitor.visit_expression_stmt(expression),
+                Stmt::Function { name, params, body, .. } => { 
                     visitor.visit_function_stmt(name, params, body)
                 },
-                Stmt::If { condition, else_branch, then_branch } => {
+                Stmt::If { condition, else_branch, then_branch, .. } => {
                     visitor.visit_if_stmt(condition, else_branch, then_branch)
                 },
-                Stmt::Print { expression } => visitor.visit_print_stmt(expression),
-                Stmt::Return { keyword, value } => visitor.visit_return_stmt(keyword, value),
-                Stmt::Var { name, initializer } => visitor.visit_var_stmt(name, initializer),
-                Stmt::While { condition, body } => visitor.visit_while_stmt(condition, body),
-                Stmt::Nil => unimplemented!(),
+                Stmt::Print { expression, .. } => visitor.visit_print_stmt(expression),
+                Stmt::Return { keyword, value, .. } => visitor.visit_return_stmt(keyword, value),
+                Stmt::Var { name, initializer, .. } => visitor.visit_var_stmt(name, initializer),
+                Stmt::While { condition, body, increment, .. } => {
+                    visitor.visit_while_stmt(condition, body, increment)
+                },
             }
         }
     }
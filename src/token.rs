@@ -1,14 +1,15 @@
 pub mod token {
-    use std::hash::{Hash, Hasher};
+    use std::borrow::Cow;
 
+    use crate::{span::span::Span, unicode::unicode};
 
     #[derive(Debug, PartialEq, Clone)]
     pub enum TokenType {
-        LeftParen, RightParen, LeftBrace, RightBrace,
-        Comma, Dot, Minus, Plus, Semicolon, Slash, Star,
+        LeftParen, RightParen, LeftBrace, RightBrace, LeftBracket, RightBracket,
+        Colon, Comma, Dot, Minus, Plus, Semicolon, Slash, Star,
         Bang, BangEqual, Equal, EqualEqual,Greater, GreaterEqual, Less, LessEqual,
         Identifier, String {literal: String}, Number{literal: f64},
-        And, Else, False, True, Fun, For, If, Nil, Or, Print, Return, Var, While, Eof
+        And, Break, Class, Continue, Else, False, True, Fun, For, If, Nil, Or, Print, Return, Super, This, Var, While, Eof
     }
 
     #[derive(Clone, Debug, PartialEq)]
@@ -16,24 +17,24 @@ pub mod token {
         pub token_type: TokenType,
         pub lexeme: String,
         pub line: i32,
+        pub span: Span,
     }
 
     impl Token {
-        pub fn new(token_type: TokenType, lexeme: String, line: i32) -> Self {
+        pub fn new(token_type: TokenType, lexeme: String, span: Span) -> Self {
             Self {
                 token_type,
                 lexeme,
-                line,
+                line: span.line,
+                span,
             }
         }
-    }
 
-    impl Hash for Token {
-        fn hash<H: Hasher>(&self, state: &mut H) {
-            self.lexeme.hash(state);
-            self.line.hash(state);
+        /// The name this token is looked up and resolved by: its lexeme
+        /// without variation selectors, so `🍕` and `🍕️` name the same
+        /// variable while diagnostics still show what was typed.
+        pub fn key(&self) -> Cow<'_, str> {
+            unicode::strip_variation_selectors(&self.lexeme)
         }
     }
-
-    impl Eq for Token {}
 }
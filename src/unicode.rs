@@ -0,0 +1,198 @@
+pub mod unicode {
+    use std::borrow::Cow;
+
+    const ZERO_WIDTH_JOINER: char = '\u{200D}';
+    const COMBINING_KEYCAP: char = '\u{20E3}';
+
+    /// Pictographic characters that may start an emoji.
+    pub fn is_emoji(character: char) -> bool {
+        matches!(
+            character as u32,
+            0x00A9 | 0x00AE | 0x203C | 0x2049 | 0x2122 | 0x2139
+                | 0x2194..=0x2199
+                | 0x21A9..=0x21AA
+                | 0x231A..=0x231B
+                | 0x2328 | 0x23CF
+                | 0x23E9..=0x23F3
+                | 0x23F8..=0x23FA
+                | 0x24C2
+                | 0x25AA..=0x25AB
+                | 0x25B6 | 0x25C0
+                | 0x25FB..=0x25FE
+                | 0x2600..=0x27BF
+                | 0x2934..=0x2935
+                | 0x2B05..=0x2B07
+                | 0x2B1B..=0x2B1C
+                | 0x2B50 | 0x2B55 | 0x3030 | 0x303D | 0x3297 | 0x3299
+                | 0x1F000..=0x1F1E5
+                | 0x1F200..=0x1F3FA
+                | 0x1F400..=0x1FAFF
+                | 0x1FC00..=0x1FFFD
+        )
+    }
+
+    /// Characters that only ever appear inside an emoji sequence: joiners,
+    /// variation selectors, skin-tone modifiers, regional indicators, tags
+    /// and the keycap mark.
+    pub fn is_emoji_component(character: char) -> bool {
+        character == ZERO_WIDTH_JOINER
+            || character == COMBINING_KEYCAP
+            || is_variation_selector(character)
+            || is_emoji_modifier(character)
+            || is_regional_indicator(character)
+            || is_tag(character)
+    }
+
+    /// Marks that combine with the letter before them, such as the accent
+    /// in a decomposed `é`.
+    pub fn is_combining_mark(character: char) -> bool {
+        matches!(
+            character as u32,
+            0x0300..=0x036F | 0x1AB0..=0x1AFF | 0x1DC0..=0x1DFF | 0x20D0..=0x20FF | 0xFE20..=0xFE2F
+        )
+    }
+
+    pub fn is_variation_selector(character: char) -> bool {
+        matches!(character, '\u{FE0E}' | '\u{FE0F}')
+    }
+
+    pub fn is_emoji_modifier(character: char) -> bool {
+        matches!(character as u32, 0x1F3FB..=0x1F3FF)
+    }
+
+    pub fn is_regional_indicator(character: char) -> bool {
+        matches!(character as u32, 0x1F1E6..=0x1F1FF)
+    }
+
+    pub fn is_tag(character: char) -> bool {
+        matches!(character as u32, 0xE0020..=0xE007F)
+    }
+
+    /// Byte length of the emoji sequence at the start of `text`, or 0 if it
+    /// does not start with one. A sequence is a flag (two regional
+    /// indicators) or a pictograph with its variation selectors, skin tone,
+    /// tags and keycap mark, optionally joined to further pictographs with
+    /// zero width joiners.
+    pub fn emoji_len(text: &str) -> usize {
+        let char_at = |offset: usize| text[offset..].chars().next();
+
+        let first = match char_at(0) {
+            Some(character) if is_emoji(character) || is_regional_indicator(character) => character,
+            _ => return 0,
+        };
+        let mut length = first.len_utf8();
+
+        if is_regional_indicator(first) {
+            if let Some(second) = char_at(length).filter(|c| is_regional_indicator(*c)) {
+                length += second.len_utf8();
+            }
+            return length;
+        }
+
+        loop {
+            while let Some(modifier) = char_at(length).filter(|c| {
+                is_variation_selector(*c) || is_emoji_modifier(*c) || is_tag(*c) || *c == COMBINING_KEYCAP
+            }) {
+                length += modifier.len_utf8();
+            }
+
+            if char_at(length) != Some(ZERO_WIDTH_JOINER) {
+                return length;
+            }
+
+            match char_at(length + ZERO_WIDTH_JOINER.len_utf8()).filter(|c| is_emoji(*c)) {
+                Some(joined) => length += ZERO_WIDTH_JOINER.len_utf8() + joined.len_utf8(),
+                None => return length,
+            }
+        }
+    }
+
+    /// Drops text and emoji presentation selectors so that `🖨` and `🖨️`
+    /// compare equal.
+    pub fn strip_variation_selectors(text: &str) -> Cow<'_, str> {
+        if text.chars().any(is_variation_selector) {
+            Cow::Owned(text.chars().filter(|c| !is_variation_selector(*c)).collect())
+        } else {
+            Cow::Borrowed(text)
+        }
+    }
+
+    /// Number of terminal columns `text` takes up. Emoji shown in emoji
+    /// presentation and East Asian wide characters take two columns,
+    /// joiners, selectors and combining marks none, and anything else one.
+    pub fn width(text: &str) -> usize {
+        let mut width = 0;
+        let mut rest = text;
+
+        while let Some(character) = rest.chars().next() {
+            let emoji = emoji_len(rest);
+            if emoji > 0 {
+                let sequence = &rest[..emoji];
+                let wide = has_emoji_presentation(character) || sequence.contains('\u{FE0F}');
+                width += if wide { 2 } else { 1 };
+                rest = &rest[emoji..];
+                continue;
+            }
+
+            width += if is_combining_mark(character) || is_emoji_component(character) {
+                0
+            } else if is_wide(character) {
+                2
+            } else {
+                1
+            };
+            rest = &rest[character.len_utf8()..];
+        }
+
+        width
+    }
+
+    /// Pictographs shown as emoji even without a variation selector. Most
+    /// of those outside the Basic Multilingual Plane are, but only some
+    /// inside it, such as ☕ and ➕ but not ▶.
+    fn has_emoji_presentation(character: char) -> bool {
+        matches!(
+            character as u32,
+            0x231A..=0x231B
+                | 0x23E9..=0x23EC
+                | 0x23F0 | 0x23F3
+                | 0x25FD..=0x25FE
+                | 0x2614..=0x2615
+                | 0x2648..=0x2653
+                | 0x267F | 0x2693 | 0x26A1
+                | 0x26AA..=0x26AB
+                | 0x26BD..=0x26BE
+                | 0x26C4..=0x26C5
+                | 0x26CE | 0x26D4 | 0x26EA
+                | 0x26F2..=0x26F3
+                | 0x26F5 | 0x26FA | 0x26FD | 0x2705
+                | 0x270A..=0x270B
+                | 0x2728 | 0x274C | 0x274E
+                | 0x2753..=0x2755
+                | 0x2757
+                | 0x2795..=0x2797
+                | 0x27B0 | 0x27BF
+                | 0x2B1B..=0x2B1C
+                | 0x2B50 | 0x2B55
+                | 0x1F000..=0x1FFFD
+        )
+    }
+
+    fn is_wide(character: char) -> bool {
+        matches!(
+            character as u32,
+            0x1100..=0x115F
+                | 0x2E80..=0x303E
+                | 0x3041..=0x33FF
+                | 0x3400..=0x4DBF
+                | 0x4E00..=0x9FFF
+                | 0xA000..=0xA4CF
+                | 0xAC00..=0xD7A3
+                | 0xF900..=0xFAFF
+                | 0xFE30..=0xFE4F
+                | 0xFF00..=0xFF60
+                | 0xFFE0..=0xFFE6
+                | 0x20000..=0x3FFFD
+        )
+    }
+}
@@ -0,0 +1,615 @@
+pub mod vm {
+    use std::{cell::RefCell, collections::HashMap, rc::Rc};
+
+    use crate::{class::class::{Class, Instance}, environment::environment::Environment, function::function::{Arity, Function, Runtime}, gc::gc, interpreter::interpreter::{Frame, Interpreter}, map::map::{Key, Map}, object::object::Object, parser::parser::Error, stdlib::stdlib, token::token::Token, unicode::unicode};
+
+    /// One instruction. Jumps are relative to the instruction after them.
+    /// Globals, properties and `super` lookups take their name from the
+    /// token the instruction was compiled from.
+    #[derive(Clone, Copy, Debug)]
+    pub enum Op {
+        Constant(u32),
+        Nil,
+        True,
+        False,
+        Pop,
+        GetLocal(u32),
+        SetLocal(u32),
+        GetUpvalue(u32),
+        SetUpvalue(u32),
+        GetGlobal,
+        DefineGlobal,
+        SetGlobal,
+        GetProperty,
+        SetProperty,
+        GetSuper,
+        Equal,
+        NotEqual,
+        Greater,
+        GreaterEqual,
+        Less,
+        LessEqual,
+        Add,
+        Subtract,
+        Multiply,
+        Divide,
+        Not,
+        Negate,
+        Print,
+        Jump(u32),
+        JumpIfFalse(u32),
+        Loop(u32),
+        Call(u32),
+        Closure(u32),
+        CloseUpvalue,
+        Return,
+        /// Builds a class named by a constant from the methods on top of the
+        /// stack, inheriting from the value below them if `inherits`.
+        Class { name: u32, methods: u32, inherits: bool },
+        List(u32),
+        Map(u32),
+        Index,
+        SetIndex,
+    }
+
+    /// The compiled code of one function. Every instruction has a site: the
+    /// token errors in it are reported at.
+    #[derive(Debug, Default)]
+    pub struct Chunk {
+        pub code: Vec<Op>,
+        sites: Vec<u32>,
+        tokens: Vec<Token>,
+        pub constants: Vec<Object>,
+        pub prototypes: Vec<Rc<Prototype>>,
+    }
+
+    impl Chunk {
+        pub fn write(&mut self, op: Op, site: Option<&Token>) {
+            if let Some(token) = site {
+                if self.tokens.last() != Some(token) {
+                    self.tokens.push(token.clone());
+                }
+            }
+            self.code.push(op);
+            self.sites.push(self.tokens.len().saturating_sub(1) as u32);
+        }
+
+        pub fn site(&self, offset: usize) -> &Token {
+            &self.tokens[self.sites[offset] as usize]
+        }
+    }
+
+    /// Where a closure finds a captured variable: a slot of the function
+    /// around it, or one of that function's own upvalues.
+    #[derive(Clone, Copy, Debug)]
+    pub struct Capture {
+        pub local: bool,
+        pub index: u32,
+    }
+
+    /// A compiled function, shared by every closure made from it.
+    #[derive(Debug)]
+    pub struct Prototype {
+        pub name: String,
+        pub arity: usize,
+        pub chunk: Chunk,
+        pub captures: Vec<Capture>,
+    }
+
+    /// A variable captured by a closure. It lives on the stack until the
+    /// scope declaring it ends, then moves into the upvalue itself.
+    #[derive(Debug)]
+    pub enum Upvalue {
+        Open(usize),
+        Closed(Object),
+    }
+
+    #[derive(Debug)]
+    pub struct Closure {
+        pub prototype: Rc<Prototype>,
+        pub upvalues: Vec<Rc<RefCell<Upvalue>>>,
+    }
+
+    struct CallFrame {
+        closure: Rc<Closure>,
+        ip: usize,
+        /// Stack index of slot 0: the function, or the receiver of a method.
+        base: usize,
+        /// Whether this frame runs the initializer of a class being called,
+        /// and so is named after the class in backtraces.
+        instantiating: bool,
+    }
+
+    /// A native function in progress, called while `depth` frames were.
+    struct NativeCall {
+        name: String,
+        depth: usize,
+    }
+
+    /// Natives calling back into scripts are the one place the VM recurses
+    /// on the native stack, once per callback. This many nested callbacks
+    /// fit in the stack of a main thread.
+    const MAX_CALLBACK_DEPTH: usize = if cfg!(debug_assertions) { 128 } else { 1024 };
+
+    /// Runs compiled chunks on a value stack.
+    pub struct Vm {
+        globals: Environment,
+        stack: Vec<Object>,
+        frames: Vec<CallFrame>,
+        natives: Vec<NativeCall>,
+        /// Upvalues still pointing at the stack, ordered by slot.
+        open_upvalues: Vec<Rc<RefCell<Upvalue>>>,
+        max_depth: usize,
+    }
+
+    impl Vm {
+        pub fn new() -> Self {
+            let mut globals = Environment::new();
+            stdlib::define(&mut globals);
+            Vm {
+                globals,
+                stack: Vec::new(),
+                frames: Vec::new(),
+                natives: Vec::new(),
+                open_upvalues: Vec::new(),
+                max_depth: Interpreter::DEFAULT_MAX_DEPTH,
+            }
+        }
+
+        /// Limits how deeply calls may nest before the script fails with a
+        /// stack overflow.
+        pub fn set_max_depth(&mut self, max_depth: usize) {
+            self.max_depth = max_depth;
+        }
+
+        pub fn interpret(&mut self, script: Rc<Prototype>) -> Result<(), Error> {
+            let closure = gc::track(Closure { prototype: script, upvalues: Vec::new() });
+            self.stack.push(Object::Callable(Function::Closure { closure: Rc::clone(&closure), receiver: None }));
+            self.frames.push(CallFrame { closure, ip: 0, base: 0, instantiating: false });
+
+            let result = self.run(0);
+            self.close_upvalues(0);
+            self.stack.clear();
+            self.frames.clear();
+            result
+        }
+
+        /// Runs until the frame count drops back to `stop`. Errors get the
+        /// backtrace of the moment they happened.
+        fn run(&mut self, stop: usize) -> Result<(), Error> {
+            let mut result = self.execute(stop);
+            if let Err(Error::Runtime { trace, .. }) = &mut result {
+                if trace.is_empty() {
+                    *trace = self.trace();
+                }
+            }
+            result
+        }
+
+        fn execute(&mut self, stop: usize) -> Result<(), Error> {
+            loop {
+                let frame = self.frames.last_mut().expect("No frame to run.");
+                let op = frame.closure.prototype.chunk.code[frame.ip];
+                frame.ip += 1;
+
+                match op {
+                    Op::Constant(index) => {
+                        let value = self.frame().closure.prototype.chunk.constants[index as usize].clone();
+                        self.stack.push(value);
+                    }
+                    Op::Nil => self.stack.push(Object::Null),
+                    Op::True => self.stack.push(Object::Boolean(true)),
+                    Op::False => self.stack.push(Object::Boolean(false)),
+                    Op::Pop => {
+                        self.pop();
+                    }
+                    Op::GetLocal(slot) => {
+                        let value = self.stack[self.frame().base + slot as usize].clone();
+                        self.stack.push(value);
+                    }
+                    Op::SetLocal(slot) => {
+                        let slot = self.frame().base + slot as usize;
+                        self.stack[slot] = self.peek(0).clone();
+                    }
+                    Op::GetUpvalue(index) => {
+                        let upvalue = Rc::clone(&self.frame().closure.upvalues[index as usize]);
+                        let value = match &*upvalue.borrow() {
+                            Upvalue::Open(slot) => self.stack[*slot].clone(),
+                            Upvalue::Closed(value) => value.clone(),
+                        };
+                        self.stack.push(value);
+                    }
+                    Op::SetUpvalue(index) => {
+                        let upvalue = Rc::clone(&self.frame().closure.upvalues[index as usize]);
+                        let value = self.peek(0).clone();
+                        let mut upvalue = upvalue.borrow_mut();
+                        match &mut *upvalue {
+                            Upvalue::Open(slot) => self.stack[*slot] = value,
+                            Upvalue::Closed(closed) => *closed = value,
+                        }
+                    }
+                    Op::GetGlobal => {
+                        let frame = self.frames.last().expect("No frame to run.");
+                        let value = self.globals.get(frame.closure.prototype.chunk.site(frame.ip - 1))?;
+                        self.stack.push(value);
+                    }
+                    Op::DefineGlobal => {
+                        let name = self.site().key().into_owned();
+                        let value = self.pop();
+                        self.globals.define(name, value);
+                    }
+                    Op::SetGlobal => {
+                        let value = self.peek(0).clone();
+                        let frame = self.frames.last().expect("No frame to run.");
+                        self.globals.assign(frame.closure.prototype.chunk.site(frame.ip - 1), value)?;
+                    }
+                    Op::GetProperty => match self.pop() {
+                        Object::Instance(instance) => {
+                            let value = Instance::get(&instance, &self.site())?;
+                            self.stack.push(value);
+                        }
+                        _ => return Err(self.error("Only instances have properties.")),
+                    },
+                    Op::SetProperty => {
+                        let value = self.pop();
+                        match self.pop() {
+                            Object::Instance(instance) => {
+                                instance.borrow_mut().set(&self.site(), value.clone());
+                                self.stack.push(value);
+                            }
+                            _ => return Err(self.error("Only instances have fields.")),
+                        }
+                    }
+                    Op::GetSuper => {
+                        let superclass = match self.pop() {
+                            Object::Class(class) => class,
+                            _ => unreachable!(),
+                        };
+                        let receiver = self.pop();
+                        let name = self.site();
+                        match superclass.find_method(&name.key()) {
+                            Some(method) => self.stack.push(Object::Callable(method.bind(receiver))),
+                            None => return Err(Error::runtime(&name, format!("Undefined property '{}'.", name.lexeme))),
+                        }
+                    }
+                    Op::Equal => {
+                        let right = self.pop();
+                        let left = self.pop();
+                        self.stack.push(Object::Boolean(left.equals(&right)));
+                    }
+                    Op::NotEqual => {
+                        let right = self.pop();
+                        let left = self.pop();
+                        self.stack.push(Object::Boolean(!left.equals(&right)));
+                    }
+                    Op::Greater => self.compare(|left, right| left > right)?,
+                    Op::GreaterEqual => self.compare(|left, right| left >= right)?,
+                    Op::Less => self.compare(|left, right| left < right)?,
+                    Op::LessEqual => self.compare(|left, right| left <= right)?,
+                    Op::Add => {
+                        let right = self.pop();
+                        let left = self.pop();
+                        match (left, right) {
+                            (Object::Number(left), Object::Number(right)) => self.stack.push(Object::Number(left + right)),
+                            (Object::String(left), Object::String(right)) => self.stack.push(Object::String([&*left, &*right].concat().into())),
+                            _ => return Err(self.error("Operands must be two numbers or two strings.")),
+                        }
+                    }
+                    Op::Subtract => self.arithmetic(|left, right| left - right)?,
+                    Op::Multiply => self.arithmetic(|left, right| left * right)?,
+                    Op::Divide => self.arithmetic(|left, right| left / right)?,
+                    Op::Not => {
+                        let value = self.pop();
+                        self.stack.push(Object::Boolean(!is_truthy(&value)));
+                    }
+                    Op::Negate => match self.pop() {
+                        Object::Number(n) => self.stack.push(Object::Number(-n)),
+                        _ => return Err(self.error("Operand must be a number.")),
+                    },
+                    Op::Print => println!("{}", self.pop()),
+                    Op::Jump(offset) => self.frame_mut().ip += offset as usize,
+                    Op::JumpIfFalse(offset) => {
+                        if !is_truthy(self.peek(0)) {
+                            self.frame_mut().ip += offset as usize;
+                        }
+                    }
+                    Op::Loop(offset) => self.frame_mut().ip -= offset as usize,
+                    Op::Call(count) => self.call_value(count as usize)?,
+                    Op::Closure(index) => {
+                        let frame = self.frame();
+                        let prototype = Rc::clone(&frame.closure.prototype.chunk.prototypes[index as usize]);
+                        let base = frame.base;
+                        let enclosing = Rc::clone(&frame.closure);
+                        let upvalues = prototype
+                            .captures
+                            .iter()
+                            .map(|capture| match capture.local {
+                                true => self.capture_upvalue(base + capture.index as usize),
+                                false => Rc::clone(&enclosing.upvalues[capture.index as usize]),
+                            })
+                            .collect();
+                        let closure = gc::track(Closure { prototype, upvalues });
+                        self.stack.push(Object::Callable(Function::Closure { closure, receiver: None }));
+                    }
+                    Op::CloseUpvalue => {
+                        self.close_upvalues(self.stack.len() - 1);
+                        self.pop();
+                    }
+                    Op::Return => {
+                        let result = self.pop();
+                        let frame = self.frames.pop().expect("No frame to return from.");
+                        self.close_upvalues(frame.base);
+                        self.stack.truncate(frame.base);
+                        self.stack.push(result);
+                        if self.frames.len() == stop {
+                            return Ok(());
+                        }
+                    }
+                    Op::Class { name, methods, inherits } => {
+                        let mut table = HashMap::new();
+                        for method in self.stack.split_off(self.stack.len() - methods as usize) {
+                            if let Object::Callable(function) = method {
+                                table.insert(unicode::strip_variation_selectors(function.name()).into_owned(), function);
+                            }
+                        }
+                        let superclass = match (inherits, self.stack.last()) {
+                            (false, _) => None,
+                            (true, Some(Object::Class(superclass))) => Some(Rc::clone(superclass)),
+                            (true, _) => return Err(self.error("Superclass must be a class.")),
+                        };
+                        let name = self.frame().closure.prototype.chunk.constants[name as usize].to_string();
+                        self.stack.push(Object::Class(gc::track(Class::new(name, superclass, table))));
+                    }
+                    Op::List(count) => {
+                        let elements = self.stack.split_off(self.stack.len() - count as usize);
+                        self.stack.push(Object::list(elements));
+                    }
+                    Op::Map(count) => {
+                        let entries = self.stack.split_off(self.stack.len() - 2 * count as usize);
+                        let mut map = Map::new();
+                        let mut entries = entries.into_iter();
+                        while let (Some(key), Some(value)) = (entries.next(), entries.next()) {
+                            let key = Key::from_object(&key).map_err(|message| self.error(message))?;
+                            map.insert(key, value);
+                        }
+                        self.stack.push(Object::map(map));
+                    }
+                    Op::Index => {
+                        let index = self.pop();
+                        let value = match self.pop() {
+                            Object::List(list) => {
+                                let list = list.borrow();
+                                let position = stdlib::list::resolve_index(list.len(), &index).map_err(|message| self.error(message))?;
+                                list[position].clone()
+                            }
+                            Object::Map(map) => {
+                                let key = Key::from_object(&index).map_err(|message| self.error(message))?;
+                                let value = map.borrow().get(&key).cloned();
+                                value.ok_or_else(|| self.error(format!("Undefined key '{}'.", index)))?
+                            }
+                            _ => return Err(self.error("Can only index lists and maps.")),
+                        };
+                        self.stack.push(value);
+                    }
+                    Op::SetIndex => {
+                        let value = self.pop();
+                        let index = self.pop();
+                        match self.pop() {
+                            Object::List(list) => {
+                                let length = list.borrow().len();
+                                let position = stdlib::list::resolve_index(length, &index).map_err(|message| self.error(message))?;
+                                list.borrow_mut()[position] = value.clone();
+                            }
+                            Object::Map(map) => {
+                                let key = Key::from_object(&index).map_err(|message| self.error(message))?;
+                                map.borrow_mut().insert(key, value.clone());
+                            }
+                            _ => return Err(self.error("Can only index lists and maps.")),
+                        }
+                        self.stack.push(value);
+                    }
+                }
+            }
+        }
+
+        /// Calls the value below the `count` arguments on top of the stack.
+        /// Closures get a new frame; everything else runs to completion.
+        fn call_value(&mut self, count: usize) -> Result<(), Error> {
+            let base = self.stack.len() - count - 1;
+            match self.stack[base].clone() {
+                Object::Callable(Function::Closure { closure, receiver }) => {
+                    if let Some(receiver) = receiver {
+                        self.stack[base] = Object::Instance(receiver);
+                    }
+                    self.call_closure(closure, count, false)
+                }
+                Object::Callable(Function::Native { name, arity, body }) => {
+                    self.check_call(arity, count)?;
+                    let arguments = self.stack.split_off(base + 1);
+                    self.stack.pop();
+
+                    self.natives.push(NativeCall { name, depth: self.frames.len() });
+                    let mut result = body(self, &arguments);
+                    if let Err(Error::Runtime { trace, .. }) = &mut result {
+                        if trace.is_empty() {
+                            *trace = self.trace();
+                        }
+                    }
+                    self.natives.pop();
+
+                    self.stack.push(result?);
+                    Ok(())
+                }
+                Object::Class(class) => {
+                    self.stack[base] = Object::Instance(gc::track(RefCell::new(Instance::new(&class))));
+                    match class.find_method("init") {
+                        Some(Function::Closure { closure, .. }) => self.call_closure(Rc::clone(closure), count, true),
+                        Some(_) => unreachable!("Classes on the VM only have compiled methods."),
+                        None => self.check_call(Arity::Exact(0), count),
+                    }
+                }
+                Object::Callable(Function::User { .. }) => unreachable!("Tree-walker functions never reach the VM."),
+                _ => Err(self.error("Can only call functions and classes.")),
+            }
+        }
+
+        fn call_closure(&mut self, closure: Rc<Closure>, count: usize, instantiating: bool) -> Result<(), Error> {
+            self.check_call(Arity::Exact(closure.prototype.arity), count)?;
+            let base = self.stack.len() - count - 1;
+            self.frames.push(CallFrame { closure, ip: 0, base, instantiating });
+            Ok(())
+        }
+
+        /// Checks the number of arguments, then that one more call fits.
+        fn check_call(&self, arity: Arity, count: usize) -> Result<(), Error> {
+            if !arity.accepts(count) {
+                return Err(self.error(format!("Expected {} but got {}.", arity, count)));
+            }
+            if self.frames.len() - 1 + self.natives.len() >= self.max_depth {
+                return Err(self.error("Stack overflow."));
+            }
+            Ok(())
+        }
+
+        fn capture_upvalue(&mut self, slot: usize) -> Rc<RefCell<Upvalue>> {
+            let position = self
+                .open_upvalues
+                .iter()
+                .rposition(|upvalue| matches!(*upvalue.borrow(), Upvalue::Open(open) if open <= slot));
+            if let Some(position) = position {
+                let upvalue = &self.open_upvalues[position];
+                if matches!(*upvalue.borrow(), Upvalue::Open(open) if open == slot) {
+                    return Rc::clone(upvalue);
+                }
+            }
+
+            let upvalue = gc::track(RefCell::new(Upvalue::Open(slot)));
+            let at = position.map_or(0, |position| position + 1);
+            self.open_upvalues.insert(at, Rc::clone(&upvalue));
+            upvalue
+        }
+
+        /// Moves every captured variable at or above `slot` off the stack.
+        fn close_upvalues(&mut self, slot: usize) {
+            while let Some(upvalue) = self.open_upvalues.last() {
+                let open = match *upvalue.borrow() {
+                    Upvalue::Open(open) if open >= slot => open,
+                    _ => break,
+                };
+                *upvalue.borrow_mut() = Upvalue::Closed(self.stack[open].clone());
+                self.open_upvalues.pop();
+            }
+        }
+
+        /// The calls in progress, outermost first, as the tree-walker
+        /// records them.
+        fn trace(&self) -> Vec<Frame> {
+            let mut trace = Vec::new();
+            let mut natives = self.natives.iter().peekable();
+            for depth in 1..=self.frames.len() {
+                let call_site = self.site_of(&self.frames[depth - 1]);
+                while let Some(native) = natives.next_if(|native| native.depth == depth) {
+                    trace.push(Frame { function: native.name.clone(), call_site: call_site.clone(), native: true });
+                }
+                if let Some(frame) = self.frames.get(depth) {
+                    let function = match (&self.stack[frame.base], frame.instantiating) {
+                        (Object::Instance(instance), true) => instance.borrow().class.name.clone(),
+                        _ => frame.closure.prototype.name.clone(),
+                    };
+                    trace.push(Frame { function, call_site, native: false });
+                }
+            }
+            trace
+        }
+
+        fn frame(&self) -> &CallFrame {
+            self.frames.last().expect("No frame to run.")
+        }
+
+        fn frame_mut(&mut self) -> &mut CallFrame {
+            self.frames.last_mut().expect("No frame to run.")
+        }
+
+        fn site_of(&self, frame: &CallFrame) -> Token {
+            frame.closure.prototype.chunk.site(frame.ip - 1).clone()
+        }
+
+        /// The token of the instruction being run.
+        fn site(&self) -> Token {
+            self.site_of(self.frame())
+        }
+
+        fn error(&self, message: impl Into<String>) -> Error {
+            Error::runtime(&self.site(), message)
+        }
+
+        fn pop(&mut self) -> Object {
+            self.stack.pop().expect("Stack underflow.")
+        }
+
+        fn peek(&self, distance: usize) -> &Object {
+            &self.stack[self.stack.len() - 1 - distance]
+        }
+
+        fn arithmetic(&mut self, operation: fn(f64, f64) -> f64) -> Result<(), Error> {
+            match (self.pop(), self.pop()) {
+                (Object::Number(right), Object::Number(left)) => {
+                    self.stack.push(Object::Number(operation(left, right)));
+                    Ok(())
+                }
+                _ => Err(self.error("Operand must be a number.")),
+            }
+        }
+
+        fn compare(&mut self, comparison: fn(f64, f64) -> bool) -> Result<(), Error> {
+            match (self.pop(), self.pop()) {
+                (Object::Number(right), Object::Number(left)) => {
+                    self.stack.push(Object::Boolean(comparison(left, right)));
+                    Ok(())
+                }
+                _ => Err(self.error("Operand must be a number.")),
+            }
+        }
+    }
+
+    impl Default for Vm {
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+
+    impl Runtime for Vm {
+        fn call(&mut self, callee: &Object, arguments: &[Object], _paren: &Token) -> Result<Object, Error> {
+            if self.natives.len() > MAX_CALLBACK_DEPTH {
+                return Err(self.error("Stack overflow."));
+            }
+            let height = self.stack.len();
+            let stop = self.frames.len();
+            self.stack.push(callee.clone());
+            self.stack.extend_from_slice(arguments);
+
+            let mut result = self.call_value(arguments.len());
+            if result.is_ok() && self.frames.len() > stop {
+                result = self.run(stop);
+            }
+
+            match result {
+                Ok(()) => Ok(self.pop()),
+                Err(error) => {
+                    self.close_upvalues(height);
+                    self.frames.truncate(stop);
+                    self.stack.truncate(height);
+                    Err(error)
+                }
+            }
+        }
+
+        fn call_site(&self) -> Token {
+            self.site()
+        }
+    }
+
+    fn is_truthy(object: &Object) -> bool {
+        !matches!(object, Object::Null | Object::Boolean(false))
+    }
+}
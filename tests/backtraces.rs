@@ -0,0 +1,50 @@
+mod common;
+
+fn backtrace_of(stderr: &str) -> Vec<&str> {
+    stderr.lines().map(str::trim).filter(|line| line.starts_with("at ") || line.starts_with("... ")).collect()
+}
+
+#[test]
+fn nested_calls_are_listed_innermost_first() {
+    let source = "fun sayHi(name) {\n  return \"Hi \" + name - 1;\n}\nfun greet() {\n  sayHi(\"you\");\n}\ngreet();\n";
+    let script = common::write_script("emojica_backtrace_nested.emojica", source);
+    let output = common::run(&script);
+    assert_eq!(output.status.code(), Some(70));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let path = script.display();
+    assert_eq!(backtrace_of(&stderr), [
+        format!("at sayHi ({}:2)", path),
+        format!("at greet ({}:5)", path),
+        format!("at <script> ({}:7)", path),
+    ], "{}", stderr);
+}
+
+#[test]
+fn natives_and_methods_get_frames() {
+    let source = "class Box {\n  init(f) { this.items = map([1], f); }\n}\nfun bad(x) {\n  return x + nil;\n}\nBox(bad);\n";
+    let stderr = common::runtime_error("emojica_backtrace_native.emojica", source);
+    let lines = backtrace_of(&stderr);
+    assert_eq!(lines.len(), 4, "{}", stderr);
+    assert!(lines[0].starts_with("at bad (") && lines[0].ends_with(":5)"), "{}", stderr);
+    assert_eq!(lines[1], "at map (native)", "{}", stderr);
+    assert!(lines[2].starts_with("at Box (") && lines[2].ends_with(":2)"), "{}", stderr);
+    assert!(lines[3].starts_with("at <script> (") && lines[3].ends_with(":7)"), "{}", stderr);
+}
+
+#[test]
+fn deep_recursion_is_truncated() {
+    let source = "fun down(n) {\n  if (n == 0) return nil + 1;\n  return down(n - 1);\n}\ndown(100);\n";
+    let stderr = common::runtime_error("emojica_backtrace_deep.emojica", source);
+    let lines = backtrace_of(&stderr);
+    assert_eq!(lines.len(), 16, "{}", stderr);
+    assert!(lines[0].ends_with(":2)"), "{}", stderr);
+    assert!(lines[1..10].iter().all(|line| line.starts_with("at down (") && line.ends_with(":3)")), "{}", stderr);
+    assert_eq!(lines[10], "... 87 more calls ...", "{}", stderr);
+    assert!(lines[15].starts_with("at <script> (") && lines[15].ends_with(":5)"), "{}", stderr);
+}
+
+#[test]
+fn top_level_errors_have_no_backtrace() {
+    let stderr = common::runtime_error("emojica_backtrace_top.emojica", "print 1 + nil;\n");
+    assert!(backtrace_of(&stderr).is_empty(), "{}", stderr);
+}
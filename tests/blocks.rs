@@ -0,0 +1,38 @@
+mod common;
+
+use std::process::Output;
+
+fn run(script: &str) -> (Output, String) {
+    let output = common::run(&common::test_path(script));
+    let stderr = String::from_utf8_lossy(&output.stderr).into_owned();
+    (output, stderr)
+}
+
+#[test]
+fn returns_leave_nested_blocks() {
+    let (output, stderr) = run("blocks/nested_returns.emojica");
+    assert_eq!(output.status.code(), Some(0), "{}", stderr);
+    assert_eq!(stderr, "");
+}
+
+#[test]
+fn errors_inside_loops_stop_the_script() {
+    let (output, stderr) = run("blocks/error_in_loop.emojica");
+    assert_eq!(output.status.code(), Some(70), "{}", stderr);
+    assert!(stderr.starts_with("error[E301]: Operands must be two numbers or two strings.\n"), "{}", stderr);
+    assert!(stderr.contains("error_in_loop.emojica:5:13\n"), "{}", stderr);
+}
+
+#[test]
+fn errors_inside_if_branches_stop_the_script() {
+    let (output, stderr) = run("blocks/error_in_if.emojica");
+    assert_eq!(output.status.code(), Some(70), "{}", stderr);
+    assert!(stderr.starts_with("error[E301]: Operand must be a number.\n"), "{}", stderr);
+    assert!(stderr.contains("error_in_if.emojica:4:14\n"), "{}", stderr);
+    assert!(stderr.contains("at check ("), "{}", stderr);
+
+    let (output, stderr) = run("blocks/error_in_else.emojica");
+    assert_eq!(output.status.code(), Some(70), "{}", stderr);
+    assert!(stderr.starts_with("error[E301]: Undefined variable 'missing'.\n"), "{}", stderr);
+    assert!(stderr.contains("error_in_else.emojica:5:11\n"), "{}", stderr);
+}
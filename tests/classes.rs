@@ -0,0 +1,51 @@
+mod common;
+
+#[test]
+fn this_outside_of_a_class() {
+    let (code, stderr) = common::status_and_stderr("emojica_this_outside.emojica", "print this;\n");
+    assert_eq!(code, Some(65), "{}", stderr);
+    assert!(stderr.contains("Cannot use 'this' outside of a class."), "{}", stderr);
+
+    let (code, stderr) = common::status_and_stderr("emojica_this_in_function.emojica", "fun f() {\n  return 🫵;\n}\n");
+    assert_eq!(code, Some(65), "{}", stderr);
+    assert!(stderr.contains("emojica_this_in_function.emojica:2:10"), "{}", stderr);
+    assert!(stderr.contains("  return 🫵;\n  |          ^^\n"), "{}", stderr);
+}
+
+#[test]
+fn returning_a_value_from_an_initializer() {
+    let (code, stderr) = common::status_and_stderr(
+        "emojica_init_return.emojica",
+        "class A {\n  init() {\n    return 1;\n  }\n}\n",
+    );
+    assert_eq!(code, Some(65), "{}", stderr);
+    assert!(stderr.contains("Cannot return value from initializer."), "{}", stderr);
+}
+
+#[test]
+fn undefined_property() {
+    let (code, stderr) = common::status_and_stderr("emojica_undefined_property.emojica", "class A {}\nprint A().missing;\n");
+    assert_eq!(code, Some(70), "{}", stderr);
+    assert!(stderr.contains("Undefined property 'missing'."), "{}", stderr);
+}
+
+#[test]
+fn properties_on_non_instances() {
+    let (code, stderr) = common::status_and_stderr("emojica_number_property.emojica", "var n = 1;\nprint n.x;\n");
+    assert_eq!(code, Some(70), "{}", stderr);
+    assert!(stderr.contains("Only instances have properties."), "{}", stderr);
+
+    let (code, stderr) = common::status_and_stderr("emojica_number_field.emojica", "var n = 1;\nn.x = 2;\n");
+    assert_eq!(code, Some(70), "{}", stderr);
+    assert!(stderr.contains("Only instances have fields."), "{}", stderr);
+}
+
+#[test]
+fn initializer_arity() {
+    let (code, stderr) = common::status_and_stderr(
+        "emojica_init_arity.emojica",
+        "class A {\n  init(a, b) {}\n}\nA(1);\n",
+    );
+    assert_eq!(code, Some(70), "{}", stderr);
+    assert!(stderr.contains("Expected 2 arguments but got 1."), "{}", stderr);
+}
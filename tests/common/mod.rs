@@ -0,0 +1,109 @@
+// Each test crate uses its own subset of these helpers.
+#![allow(dead_code)]
+
+use std::{ffi::OsStr, fs, ops::Deref, path::{Path, PathBuf}, process::{self, Command, Output}, sync::atomic::{AtomicUsize, Ordering}};
+
+/// Runs the emojica binary with the given arguments and captures everything
+/// it printed.
+pub fn run_with<I: IntoIterator<Item = S>, S: AsRef<OsStr>>(arguments: I) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_emojica"))
+        .args(arguments)
+        .output()
+        .expect("Failed to run emojica.")
+}
+
+/// Runs emojica under `ulimit -v`, like a memory-limited container would.
+#[cfg(unix)]
+pub fn run_limited(kilobytes: usize, arguments: &[&OsStr]) -> Output {
+    Command::new("sh")
+        .arg("-c")
+        .arg(format!("ulimit -v {} && exec \"$0\" \"$@\"", kilobytes))
+        .arg(env!("CARGO_BIN_EXE_emojica"))
+        .args(arguments)
+        .output()
+        .expect("Failed to run emojica.")
+}
+
+pub fn run(path: &Path) -> Output {
+    run_with([path])
+}
+
+/// Writes `source` to a scratch file named `name` and runs it.
+pub fn run_source(name: &str, source: &str) -> Output {
+    run(&write_script(name, source))
+}
+
+/// Runs `source` and returns its exit code and stderr.
+pub fn status_and_stderr(name: &str, source: &str) -> (Option<i32>, String) {
+    let output = run_source(name, source);
+    (output.status.code(), String::from_utf8_lossy(&output.stderr).into_owned())
+}
+
+/// Runs `source`, expects it to stop with a runtime error and returns stderr.
+pub fn runtime_error(name: &str, source: &str) -> String {
+    let output = run_source(name, source);
+    let stderr = String::from_utf8_lossy(&output.stderr).into_owned();
+    assert_eq!(output.status.code(), Some(70), "{}", stderr);
+    stderr
+}
+
+/// A scratch script, deleted again when it goes out of scope.
+pub struct Script {
+    path: PathBuf,
+}
+
+impl Deref for Script {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for Script {
+    fn drop(&mut self) {
+        if let Some(directory) = self.path.parent() {
+            let _ = fs::remove_dir_all(directory);
+        }
+    }
+}
+
+/// Writes `source` to a scratch file called `name`, inside a directory named
+/// after the process id and a counter so test crates running side by side
+/// never share a file.
+pub fn write_script(name: &str, source: &str) -> Script {
+    static WRITTEN: AtomicUsize = AtomicUsize::new(0);
+    let unique = format!("emojica-{}-{}", process::id(), WRITTEN.fetch_add(1, Ordering::Relaxed));
+    let directory = std::env::temp_dir().join(unique);
+    fs::create_dir_all(&directory).expect("Could not create script directory.");
+    let path = directory.join(name);
+    fs::write(&path, source).expect("Could not write script.");
+    Script { path }
+}
+
+pub fn test_path(relative: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join(relative)
+}
+
+/// Every script under `tests/` and `examples/`, in order.
+pub fn all_scripts() -> Vec<PathBuf> {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let mut found = Vec::new();
+    scripts(&root.join("tests"), &mut found);
+    scripts(&root.join("examples"), &mut found);
+    found.sort();
+    found
+}
+
+/// Every script below `directory`, including its subdirectories.
+fn scripts(directory: &Path, found: &mut Vec<PathBuf>) {
+    let entries = fs::read_dir(directory).unwrap_or_else(|_| panic!("Unreadable directory {}", directory.display()));
+    for entry in entries {
+        let path = entry.expect("Unreadable directory entry.").path();
+        if path.is_dir() {
+            scripts(&path, found);
+        } else if path.extension().is_some_and(|extension| extension == "emojica") {
+            found.push(path);
+        }
+    }
+}
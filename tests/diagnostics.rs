@@ -0,0 +1,120 @@
+mod common;
+
+use std::process::Command;
+
+#[test]
+fn parse_errors_underline_the_token_found() {
+    let (code, stderr) = common::status_and_stderr("emojica_diagnostic_parse.emojica", "var 🍕 = 1;\nprint 🍕 2;\n");
+    assert_eq!(code, Some(65), "{}", stderr);
+    assert!(stderr.starts_with("error[E101]: Expect ';' (🔚) after value.\n --> "), "{}", stderr);
+    assert!(stderr.contains(concat!(
+        "emojica_diagnostic_parse.emojica:2:9\n",
+        "  |\n",
+        "2 | print 🍕 2;\n",
+        "  |          ^ found '2'\n",
+    )), "{}", stderr);
+}
+
+#[test]
+fn end_of_file_is_named() {
+    let (_, stderr) = common::status_and_stderr("emojica_diagnostic_eof.emojica", "print 1 +");
+    assert!(stderr.contains("error[E102]: Expect expression."), "{}", stderr);
+    assert!(stderr.contains("1 | print 1 +\n  |          ^ found end of file\n"), "{}", stderr);
+}
+
+#[test]
+fn underlines_match_the_width_of_wide_characters() {
+    let source = "var 👩‍👩‍👧 = \"家族\";\nprint 👩‍👩‍👧 ➖ 1;\n";
+    let (code, stderr) = common::status_and_stderr("emojica_diagnostic_wide.emojica", source);
+    assert_eq!(code, Some(70), "{}", stderr);
+    assert!(stderr.contains("error[E301]: Operand must be a number."), "{}", stderr);
+    assert!(stderr.contains("emojica_diagnostic_wide.emojica:2:13\n"), "{}", stderr);
+    assert!(stderr.contains("2 | print 👩‍👩‍👧 ➖ 1;\n  |          ^^\n"), "{}", stderr);
+
+    let source = "var 名前 = 1;\nprint 名前 + nil;\n";
+    let (_, stderr) = common::status_and_stderr("emojica_diagnostic_cjk.emojica", source);
+    assert!(stderr.contains("2 | print 名前 + nil;\n  |            ^\n"), "{}", stderr);
+}
+
+#[test]
+fn multi_line_spans_underline_their_first_line() {
+    let (code, stderr) = common::status_and_stderr("emojica_diagnostic_string.emojica", "var a = 1;\nvar s = \"ab\ncd\n");
+    assert_eq!(code, Some(65), "{}", stderr);
+    assert!(stderr.starts_with("error[E002]: Unterminated string.\n"), "{}", stderr);
+    assert!(stderr.contains("2 | var s = \"ab\n  |         ^ this string is never closed\n"), "{}", stderr);
+}
+
+#[test]
+fn resolver_errors_carry_notes_and_help() {
+    let source = "class A {\n  f() {\n    return super.f();\n  }\n}\n";
+    let (code, stderr) = common::status_and_stderr("emojica_diagnostic_super.emojica", source);
+    assert_eq!(code, Some(65), "{}", stderr);
+    assert!(stderr.starts_with("error[E207]: Cannot use 'super' in a class with no superclass.\n"), "{}", stderr);
+    assert!(stderr.contains(concat!(
+        "3 |     return super.f();\n",
+        "  |            ^^^^^\n",
+        "  = help: Name a superclass after '<' (◀️) in the class declaration.\n",
+    )), "{}", stderr);
+
+    let source = "class A {\n  init() {\n    return 1;\n  }\n}\n";
+    let (_, stderr) = common::status_and_stderr("emojica_diagnostic_init.emojica", source);
+    assert!(stderr.contains("  = note: Initializers always return the new instance.\n"), "{}", stderr);
+}
+
+#[test]
+fn every_resolver_error_is_reported() {
+    let (_, stderr) = common::status_and_stderr("emojica_diagnostic_many.emojica", "print this;\nreturn 1;\n");
+    assert!(stderr.contains("error[E205]"), "{}", stderr);
+    assert!(stderr.contains("error[E203]"), "{}", stderr);
+}
+
+#[test]
+fn invalid_assignment_targets_are_underlined() {
+    let (code, stderr) = common::status_and_stderr("emojica_diagnostic_assign.emojica", "(1 + 2 = 3);\n");
+    assert_eq!(code, Some(65), "{}", stderr);
+    assert!(stderr.contains("emojica_diagnostic_assign.emojica:1:2\n"), "{}", stderr);
+    assert!(stderr.contains("1 | (1 + 2 = 3);\n  |  ^^^^^ this cannot be assigned to\n"), "{}", stderr);
+    assert!(!stderr.contains("found"), "{}", stderr);
+}
+
+#[test]
+fn gutter_widens_with_the_line_number() {
+    let source = format!("{}print nope;\n", "\n".repeat(11));
+    let (_, stderr) = common::status_and_stderr("emojica_diagnostic_gutter.emojica", &source);
+    assert!(stderr.contains("  --> "), "{}", stderr);
+    assert!(stderr.contains("   |\n12 | print nope;\n   |       ^^^^\n"), "{}", stderr);
+}
+
+#[test]
+fn colour_can_be_forced_and_disabled() {
+    let script = common::write_script("emojica_diagnostic_colour.emojica", "print nope;\n");
+    let run = |variables: &[(&str, &str)]| {
+        let output = Command::new(env!("CARGO_BIN_EXE_emojica"))
+            .arg(&*script)
+            .env_remove("NO_COLOR")
+            .env_remove("CLICOLOR_FORCE")
+            .envs(variables.iter().copied())
+            .output()
+            .expect("Failed to run emojica.");
+        String::from_utf8_lossy(&output.stderr).into_owned()
+    };
+
+    assert!(!run(&[]).contains('\x1b'));
+    assert!(run(&[("CLICOLOR_FORCE", "1")]).contains("\x1b[1;31merror[E301]\x1b[0m"));
+    assert!(!run(&[("CLICOLOR_FORCE", "1"), ("NO_COLOR", "1")]).contains('\x1b'));
+}
+
+#[test]
+fn missing_scripts_are_reported_without_a_panic() {
+    let missing = common::test_path("missing/nope.emojica");
+    for flags in [&[][..], &["--vm"][..], &["--asciify"][..]] {
+        let mut arguments: Vec<&std::ffi::OsStr> = flags.iter().map(|flag| flag.as_ref()).collect();
+        arguments.push(missing.as_os_str());
+        let output = common::run_with(arguments);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+
+        assert_eq!(output.status.code(), Some(66), "{}", stderr);
+        assert!(stderr.starts_with(&format!("Could not read '{}': ", missing.display())), "{}", stderr);
+        assert!(!stderr.contains("panicked"), "{}", stderr);
+    }
+}
@@ -0,0 +1,186 @@
+//! Runs every `.emojica` script under `tests/` and `examples/` and checks
+//! what it printed against the expectations written in its comments:
+//!
+//! - `// expect: 3` is a line of stdout, in order.
+//! - `// expect runtime error: Operand must be a number.` is the runtime
+//!   error the script stops with, reported on the line of the comment.
+//! - `// expect error: Expect expression.` is an error found before the
+//!   script runs, reported on the line of the comment.
+//!
+//! The exit code follows from those: 70 after a runtime error, 65 after any
+//! other error, 0 otherwise. Scripts without any expectations, such as the
+//! examples that print thousands of lines, only have to run cleanly.
+//!
+//! Every script runs on both the tree-walker and the bytecode VM. Run with
+//! `EMOJICA_BLESS=1` to rewrite the expectations of every failing script
+//! from what it actually did.
+
+mod common;
+
+use std::{fs, path::Path};
+
+const EXPECT_OUTPUT: &str = "// expect: ";
+const EXPECT_RUNTIME_ERROR: &str = "// expect runtime error: ";
+const EXPECT_ERROR: &str = "// expect error: ";
+
+#[derive(Debug, PartialEq)]
+enum Kind {
+    Compile,
+    Runtime,
+}
+
+/// An error diagnostic: its kind, message and the line it points at.
+#[derive(Debug, PartialEq)]
+struct Expected {
+    kind: Kind,
+    message: String,
+    line: usize,
+}
+
+#[derive(Debug, PartialEq)]
+struct Outcome {
+    stdout: String,
+    errors: Vec<Expected>,
+    code: Option<i32>,
+}
+
+impl Outcome {
+    fn expected(source: &str) -> Option<Self> {
+        let mut stdout = String::new();
+        let mut errors = Vec::new();
+        for (index, line) in source.lines().enumerate() {
+            if let Some((_, expected)) = line.split_once(EXPECT_OUTPUT) {
+                stdout.push_str(expected);
+                stdout.push('\n');
+            } else if let Some((_, message)) = line.split_once(EXPECT_RUNTIME_ERROR) {
+                errors.push(Expected { kind: Kind::Runtime, message: message.to_string(), line: index + 1 });
+            } else if let Some((_, message)) = line.split_once(EXPECT_ERROR) {
+                errors.push(Expected { kind: Kind::Compile, message: message.to_string(), line: index + 1 });
+            }
+        }
+
+        if stdout.is_empty() && errors.is_empty() {
+            return None;
+        }
+        let code = match errors.first() {
+            Some(Expected { kind: Kind::Runtime, .. }) => 70,
+            Some(Expected { kind: Kind::Compile, .. }) => 65,
+            None => 0,
+        };
+        Some(Outcome { stdout, errors, code: Some(code) })
+    }
+
+    fn actual(script: &Path, flags: &[&str]) -> Self {
+        let mut arguments: Vec<&std::ffi::OsStr> = flags.iter().map(|flag| flag.as_ref()).collect();
+        arguments.push(script.as_os_str());
+        let output = common::run_with(arguments);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let mut errors = Vec::new();
+        let mut lines = stderr.lines();
+        while let Some(line) = lines.next() {
+            let Some((code, message)) = line.strip_prefix("error[").and_then(|rest| rest.split_once("]: ")) else {
+                continue;
+            };
+            let line = lines
+                .next()
+                .and_then(|location| location.rsplit(':').nth(1))
+                .and_then(|line| line.parse().ok())
+                .unwrap_or(0);
+            let kind = if code == "E301" { Kind::Runtime } else { Kind::Compile };
+            errors.push(Expected { kind, message: message.to_string(), line });
+        }
+
+        Outcome {
+            stdout: String::from_utf8_lossy(&output.stdout).into_owned(),
+            errors,
+            code: output.status.code(),
+        }
+    }
+}
+
+/// Rewrites the expectations of `source` to describe `actual`. Each existing
+/// `// expect:` line takes the next line of output in place, extra output
+/// goes after the last of them, and errors go on the line they are
+/// reported at.
+fn bless(source: &str, actual: &Outcome) -> String {
+    let mut output = actual.stdout.lines();
+    let last_output = source.lines().enumerate().filter(|(_, line)| line.contains(EXPECT_OUTPUT)).map(|(index, _)| index).last();
+    let mut blessed = String::new();
+    for (index, line) in source.lines().enumerate() {
+        let marker = [EXPECT_OUTPUT, EXPECT_RUNTIME_ERROR, EXPECT_ERROR]
+            .into_iter()
+            .find_map(|marker| line.split_once(marker).map(|(code, _)| (marker, code)));
+        let mut text = match marker {
+            Some((EXPECT_OUTPUT, code)) => match output.next() {
+                Some(printed) => format!("{}{}{}", code, EXPECT_OUTPUT, printed),
+                None => code.trim_end().to_string(),
+            },
+            Some((_, code)) => code.trim_end().to_string(),
+            None => line.to_string(),
+        };
+        let errors: Vec<&Expected> = actual.errors.iter().filter(|error| error.line == index + 1).collect();
+        if marker.is_some() && text.is_empty() && errors.is_empty() {
+            continue;
+        }
+
+        for error in errors {
+            if !text.is_empty() {
+                text.push(' ');
+            }
+            text.push_str(if error.kind == Kind::Runtime { EXPECT_RUNTIME_ERROR } else { EXPECT_ERROR });
+            text.push_str(&error.message);
+        }
+        blessed.push_str(&text);
+        blessed.push('\n');
+        if last_output == Some(index) {
+            push_output(&mut blessed, &mut output);
+        }
+    }
+    push_output(&mut blessed, &mut output);
+    blessed
+}
+
+/// Appends the rest of `output` as `// expect:` lines.
+fn push_output<'a>(blessed: &mut String, output: &mut impl Iterator<Item = &'a str>) {
+    for line in output {
+        blessed.push_str(EXPECT_OUTPUT);
+        blessed.push_str(line);
+        blessed.push('\n');
+    }
+}
+
+/// Checks every script, running the binary with `flags` before its path.
+fn check(flags: &[&str]) {
+    let found = common::all_scripts();
+    let blessing = std::env::var_os("EMOJICA_BLESS").is_some();
+    let mut failures = Vec::new();
+    for script in &found {
+        let source = fs::read_to_string(script).expect("Unreadable script.");
+        let actual = Outcome::actual(script, flags);
+        let passed = match Outcome::expected(&source) {
+            Some(expected) => expected == actual,
+            None => actual.code == Some(0) && actual.errors.is_empty(),
+        };
+
+        if passed {
+            continue;
+        }
+        if blessing {
+            fs::write(script, bless(&source, &actual)).expect("Could not bless script.");
+        } else {
+            failures.push(format!("{}\n  expected: {:?}\n  actual: {:?}", script.display(), Outcome::expected(&source), actual));
+        }
+    }
+
+    assert!(failures.is_empty(), "{} of {} scripts failed with {:?}:\n{}", failures.len(), found.len(), flags, failures.join("\n"));
+}
+
+#[test]
+fn golden() {
+    check(&[]);
+}
+
+#[test]
+fn golden_vm() {
+    check(&["--vm"]);
+}
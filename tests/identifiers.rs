@@ -0,0 +1,50 @@
+mod common;
+
+#[test]
+fn stray_joiner_is_not_an_identifier() {
+    let output = common::run_source("emojica_stray_joiner.emojica", "var a = 1;\n\u{200D} = 2;\n");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert_eq!(output.status.code(), Some(65), "{}", stderr);
+    assert!(stderr.contains("emojica_stray_joiner.emojica:2:1"), "{}", stderr);
+}
+
+#[test]
+fn keycap_is_not_an_identifier() {
+    let output = common::run_source("emojica_keycap.emojica", "var 1️⃣ = 1;\n");
+
+    assert_eq!(output.status.code(), Some(65), "{}", String::from_utf8_lossy(&output.stderr));
+}
+
+#[test]
+fn resolver_treats_both_spellings_as_one_name() {
+    let output = common::run_source(
+        "emojica_shadowed_spelling.emojica",
+        "{\n  var ☕ = 1;\n  var ☕️ = 2;\n}\n",
+    );
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(stderr.contains("already declared in this scope"), "{}", stderr);
+}
+
+#[test]
+fn both_spellings_name_one_variable_but_errors_show_the_typed_one() {
+    let source = concat!(
+        "var ☕️ = 1;\n",
+        "class Cup {\n  ☕() { return this.🫖️; }\n}\n",
+        "var cup = Cup();\ncup.🫖 = ☕;\n",
+        "print cup.☕️();\n",
+        "print 🍵️;\n",
+    );
+    let script = common::write_script("emojica_both_spellings.emojica", source);
+    for flags in [&[][..], &["--vm"][..]] {
+        let mut arguments: Vec<&std::ffi::OsStr> = flags.iter().map(|flag| flag.as_ref()).collect();
+        arguments.push(script.as_os_str());
+        let output = common::run_with(arguments);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+
+        assert_eq!(output.status.code(), Some(70), "{}", stderr);
+        assert_eq!(String::from_utf8_lossy(&output.stdout), "1\n");
+        assert!(stderr.contains("Undefined variable '🍵️'."), "{}", stderr);
+    }
+}
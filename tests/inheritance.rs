@@ -0,0 +1,39 @@
+mod common;
+
+#[test]
+fn inheriting_from_itself() {
+    let (code, stderr) = common::status_and_stderr("emojica_inherit_self.emojica", "class A < A {}\n");
+    assert_eq!(code, Some(65), "{}", stderr);
+    assert!(stderr.contains("A class cannot inherit from itself."), "{}", stderr);
+}
+
+#[test]
+fn inheriting_from_a_non_class() {
+    let (code, stderr) = common::status_and_stderr("emojica_inherit_number.emojica", "var A = 1;\nclass B < A {}\n");
+    assert_eq!(code, Some(70), "{}", stderr);
+    assert!(stderr.contains("Superclass must be a class."), "{}", stderr);
+}
+
+#[test]
+fn super_outside_of_a_subclass() {
+    let (code, stderr) = common::status_and_stderr("emojica_super_top_level.emojica", "super.method();\n");
+    assert_eq!(code, Some(65), "{}", stderr);
+    assert!(stderr.contains("Cannot use 'super' outside of a class."), "{}", stderr);
+
+    let (code, stderr) = common::status_and_stderr(
+        "emojica_super_no_superclass.emojica",
+        "class A {\n  method() {\n    super.method();\n  }\n}\n",
+    );
+    assert_eq!(code, Some(65), "{}", stderr);
+    assert!(stderr.contains("Cannot use 'super' in a class with no superclass."), "{}", stderr);
+}
+
+#[test]
+fn undefined_super_method() {
+    let (code, stderr) = common::status_and_stderr(
+        "emojica_super_missing.emojica",
+        "class A {}\nclass B < A {\n  method() {\n    super.missing();\n  }\n}\nB().method();\n",
+    );
+    assert_eq!(code, Some(70), "{}", stderr);
+    assert!(stderr.contains("Undefined property 'missing'."), "{}", stderr);
+}
@@ -0,0 +1,56 @@
+mod common;
+
+use std::path::Path;
+
+fn format(flag: &str, path: &Path) -> String {
+    let output = common::run_with([flag.as_ref(), path.as_os_str()]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    String::from_utf8(output.stdout).expect("Formatter printed invalid UTF-8.")
+}
+
+#[test]
+fn asciify_spells_out_every_keyword() {
+    let ascii = format("--asciify", &common::test_path("keywords/emoji_program.emojica"));
+
+    assert!(ascii.starts_with("var count = 0;\nwhile (count < 3) {\n  print count;"), "{}", ascii);
+    assert!(ascii.contains("fun greet(name, excited) {"), "{}", ascii);
+    assert!(ascii.contains("if (excited and true) return name + \"!\";"), "{}", ascii);
+    assert!(ascii.contains("for (var i = 0;\n    i < 2;"), "{}", ascii);
+    assert!(ascii.contains("if (nil or false) print \"yes\"; else print \"no\";"), "{}", ascii);
+    for keyword in ["📦", "🔁", "🖨️", "🧑‍💻", "❓", "↩️", "👍", "👎", "🔂", "🕳️", "🔀", "↪️", "🤝"] {
+        assert!(!ascii.contains(keyword), "{} left in {}", keyword, ascii);
+    }
+}
+
+#[test]
+fn emojify_round_trips() {
+    let script = common::test_path("keywords/emoji_program.emojica");
+    let ascii_script = common::write_script("emojica_round_trip.emojica", &format("--asciify", &script));
+
+    assert_eq!(format("--emojify", &ascii_script), format("--emojify", &script));
+}
+
+/// The tokens of a script whatever they are spelled with: its emoji
+/// spelling, without the spaces the formatter may add between them.
+fn tokens(path: &Path) -> String {
+    format("--emojify", path).split_whitespace().collect()
+}
+
+#[test]
+fn asciify_keeps_the_tokens_of_every_script() {
+    let adjacent = common::write_script("emojica_adjacent_operators.emojica", "print 1🔸5 ⬅️⬅️ 2➗➗3 ◀️⬅️ x🔸y;\n");
+    let mut scripts = common::all_scripts();
+    scripts.push(adjacent.to_path_buf());
+    for script in scripts {
+        let ascii_script = common::write_script("emojica_round_trip_tokens.emojica", &format("--asciify", &script));
+
+        assert_eq!(tokens(&ascii_script), tokens(&script), "{}", script.display());
+    }
+}
+
+#[test]
+fn formatter_leaves_strings_and_comments_alone() {
+    let script = common::write_script("emojica_strings_and_comments.emojica", "print \"print while\"; // var fun\n");
+
+    assert_eq!(format("--emojify", &script), "🖨️ \"print while\"🔚 // var fun\n");
+}
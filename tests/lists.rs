@@ -0,0 +1,43 @@
+mod common;
+
+#[test]
+fn index_out_of_range() {
+    let stderr = common::runtime_error("emojica_list_get_range.emojica", "var xs = [1, 2];\nprint xs[2];\n");
+    assert!(stderr.contains("List index 2 out of range for length 2."), "{}", stderr);
+
+    let stderr = common::runtime_error("emojica_list_negative_range.emojica", "var xs = [1, 2];\nprint xs[-3];\n");
+    assert!(stderr.contains("List index -3 out of range for length 2."), "{}", stderr);
+
+    let stderr = common::runtime_error("emojica_list_set_range.emojica", "var xs = [];\nxs[0] = 1;\n");
+    assert!(stderr.contains("List index 0 out of range for length 0."), "{}", stderr);
+}
+
+#[test]
+fn index_must_be_an_integer() {
+    let stderr = common::runtime_error("emojica_list_float_index.emojica", "print [1, 2][0.5];\n");
+    assert!(stderr.contains("List index must be an integer."), "{}", stderr);
+
+    let stderr = common::runtime_error("emojica_list_string_index.emojica", "print [1, 2][\"0\"];\n");
+    assert!(stderr.contains("List index must be an integer."), "{}", stderr);
+}
+
+#[test]
+fn indexing_a_non_list() {
+    let stderr = common::runtime_error("emojica_index_number.emojica", "var n = 1;\nprint n[0];\n");
+    assert!(stderr.contains("Can only index lists and maps."), "{}", stderr);
+}
+
+#[test]
+fn native_errors() {
+    let stderr = common::runtime_error("emojica_pop_empty.emojica", "pop([]);\n");
+    assert!(stderr.contains("Cannot pop from an empty list."), "{}", stderr);
+
+    let stderr = common::runtime_error("emojica_insert_range.emojica", "insert([1], 3, 0);\n");
+    assert!(stderr.contains("Insert index 3 out of range for length 1."), "{}", stderr);
+
+    let stderr = common::runtime_error("emojica_sort_mixed.emojica", "sort([1, \"a\"]);\n");
+    assert!(stderr.contains("Can only sort a list of numbers or a list of strings."), "{}", stderr);
+
+    let stderr = common::runtime_error("emojica_push_number.emojica", "push(1, 2);\n");
+    assert!(stderr.contains("Can only push onto a list."), "{}", stderr);
+}
@@ -0,0 +1,12 @@
+mod common;
+
+#[test]
+fn loop_control_outside_loops() {
+    let source = "break;\nwhile (true) { fun f() { continue; } break; }\n";
+    let output = common::run_source("emojica_loops_outside.emojica", source);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(65), "{}", stderr);
+    assert!(stderr.contains("error[E209]: Cannot use 'break' outside of a loop.\n"), "{}", stderr);
+    assert!(stderr.contains("error[E210]: Cannot use 'continue' outside of a loop.\n"), "{}", stderr);
+    assert!(stderr.contains("/emojica_loops_outside.emojica:2:26\n"), "{}", stderr);
+}
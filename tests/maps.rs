@@ -0,0 +1,27 @@
+mod common;
+
+#[test]
+fn missing_key() {
+    let stderr = common::runtime_error("emojica_map_missing.emojica", "var m = {\"a\": 1};\nprint m[\"b\"];\n");
+    assert!(stderr.contains("Undefined key 'b'."), "{}", stderr);
+}
+
+#[test]
+fn unhashable_keys() {
+    let stderr = common::runtime_error("emojica_map_list_key.emojica", "var m = {};\nm[[1]] = 2;\n");
+    assert!(stderr.contains("Map keys must be strings, numbers or booleans."), "{}", stderr);
+
+    let stderr = common::runtime_error("emojica_map_nil_key.emojica", "print {nil: 1};\n");
+    assert!(stderr.contains("Map keys must be strings, numbers or booleans."), "{}", stderr);
+
+    let stderr = common::runtime_error("emojica_map_nan_key.emojica", "var m = {};\nprint has(m, 0/0);\n");
+    assert!(stderr.contains("NaN cannot be a map key."), "{}", stderr);
+}
+
+#[test]
+fn missing_colon() {
+    let output = common::run_source("emojica_map_colon.emojica", "var m = {\"a\" 1};\n");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Expect ':'"), "{}", stderr);
+    assert!(stderr.contains("after map key."), "{}", stderr);
+}
@@ -0,0 +1,35 @@
+mod common;
+
+fn format(flag: &str, source: &str) -> String {
+    let script = common::write_script("emojica_format_operators.emojica", source);
+    String::from_utf8_lossy(&common::run_with([flag.as_ref(), script.as_os_str()]).stdout).into_owned()
+}
+
+#[test]
+fn diagnostics_show_the_spelling_that_was_typed() {
+    let output = common::run_source("emojica_operator_lexeme.emojica", "🖨️ 1 ➕ ➕;\n");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("1 | 🖨️ 1 ➕ ➕;\n  |         ^^ found '➕'\n"), "{}", stderr);
+
+    let output = common::run_source("emojica_missing_delimiter.emojica", "🖨️ 1\n");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Expect ';' (🔚) after value."), "{}", stderr);
+}
+
+#[test]
+fn formatter_respells_operators_but_not_numbers() {
+    assert_eq!(
+        format("--emojify", "print (1.5 + x) != 2 == y;\n"),
+        "🖨️ 🌜1.5 ➕ x🌛 ❗🟰 2 🟰 y🔚\n"
+    );
+}
+
+#[test]
+fn formatter_keeps_adjacent_operators_apart() {
+    assert_eq!(format("--asciify", "x ⬅️⬅️ y ➗➗ z ◀️⬅️ w;\n"), "x = = y / / z < = w;\n");
+}
+
+#[test]
+fn formatter_keeps_a_dot_between_numbers_apart() {
+    assert_eq!(format("--asciify", "print 1🔸5 🔸 2.5🔸x;\n"), "print 1 .5 . 2.5.x;\n");
+}
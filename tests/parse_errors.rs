@@ -0,0 +1,70 @@
+mod common;
+
+fn errors_in(name: &str, source: &str) -> Vec<String> {
+    let output = common::run_source(name, source);
+    let stderr = String::from_utf8_lossy(&output.stderr).into_owned();
+    assert_eq!(output.status.code(), Some(65), "{}", stderr);
+    assert!(output.stdout.is_empty(), "{}", String::from_utf8_lossy(&output.stdout));
+    stderr
+        .lines()
+        .filter(|line| line.starts_with("error["))
+        .map(str::to_string)
+        .collect()
+}
+
+#[test]
+fn every_statement_error_is_reported() {
+    let source = "print 1 +;\nprint \"fine\";\nvar = 2;\nprint 3\nprint 4;\n";
+    let errors = errors_in("emojica_parse_many.emojica", source);
+    assert_eq!(
+        errors,
+        [
+            "error[E102]: Expect expression.",
+            "error[E101]: Expect variable name.",
+            "error[E101]: Expect ';' (🔚) after value.",
+        ],
+    );
+}
+
+#[test]
+fn recovers_inside_blocks() {
+    let source = "{\n  var a = ;\n  print a;\n  a = ;\n}\nprint ;\n";
+    let errors = errors_in("emojica_parse_block.emojica", source);
+    assert_eq!(errors.len(), 3, "{:?}", errors);
+    assert!(errors.iter().all(|error| error == "error[E102]: Expect expression."), "{:?}", errors);
+}
+
+#[test]
+fn broken_bodies_are_skipped_whole() {
+    let source = "fun f( {\n  print 1;\n  if (x) { print 2; }\n}\nclass {\n  m() {}\n}\nprint 3;\n";
+    let errors = errors_in("emojica_parse_bodies.emojica", source);
+    assert_eq!(
+        errors,
+        ["error[E101]: Expect parameter name.", "error[E101]: Expect class name."],
+    );
+}
+
+#[test]
+fn stray_tokens_are_reported_once() {
+    let errors = errors_in("emojica_parse_stray.emojica", "}\n) ]\nprint 1;\n");
+    assert_eq!(errors.len(), 2, "{:?}", errors);
+}
+
+#[test]
+fn unclosed_block() {
+    let errors = errors_in("emojica_parse_unclosed.emojica", "{\n  print 1;\n");
+    assert_eq!(errors, ["error[E101]: Expect '}' (👈) after block."]);
+}
+
+#[test]
+fn errors_that_do_not_need_recovery() {
+    let errors = errors_in("emojica_parse_assignment.emojica", "var a = 1;\n1 = a;\n(a) = 2;\nprint a = ;\n");
+    assert_eq!(
+        errors,
+        [
+            "error[E103]: Invalid assignment target.",
+            "error[E103]: Invalid assignment target.",
+            "error[E102]: Expect expression.",
+        ],
+    );
+}
@@ -0,0 +1,22 @@
+mod common;
+
+#[test]
+fn unexpected_character_after_emoji_reports_its_line() {
+    let script = common::test_path("scanner/errors/unexpected_character.emojica");
+    let output = common::run(&script);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert_eq!(output.status.code(), Some(65), "{}", stderr);
+    assert!(stderr.contains("unexpected_character.emojica:3:"), "{}", stderr);
+    assert!(stderr.contains("Unexpected character '§'."), "{}", stderr);
+}
+
+#[test]
+fn unterminated_string_with_emoji_does_not_panic() {
+    let script = common::test_path("scanner/errors/unterminated_string.emojica");
+    let output = common::run(&script);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(stderr.contains("Unterminated string."), "{}", stderr);
+    assert!(!stderr.contains("panicked"), "{}", stderr);
+}
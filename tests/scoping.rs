@@ -0,0 +1,31 @@
+mod common;
+
+use std::{io::Write, process::{Command, Stdio}};
+
+#[test]
+fn own_initializer() {
+    let output = common::run_source("emojica_scoping_initializer.emojica", "{ var a = 1; { var a = a; } }\n");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(65), "{}", stderr);
+    assert!(stderr.contains("Cannot read local variable in its own initializer."), "{}", stderr);
+}
+
+#[test]
+fn functions_keep_their_locals_across_prompt_lines() {
+    let lines = concat!(
+        "fun counter() { var n = 0; fun next() { n = n + 1; return n; } return next; }\n",
+        "var c = counter();\n",
+        "{ var x = 10; print x + c(); }\n",
+        "print c();\n",
+    );
+    let mut child = Command::new(env!("CARGO_BIN_EXE_emojica"))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("Failed to run emojica.");
+    child.stdin.take().expect("No stdin.").write_all(lines.as_bytes()).expect("Could not write to the prompt.");
+    let output = child.wait_with_output().expect("Failed to run emojica.");
+
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "> > 11\n> 2\n> ", "{}", String::from_utf8_lossy(&output.stderr));
+}
@@ -0,0 +1,29 @@
+mod common;
+
+#[test]
+fn points_at_the_operator() {
+    let stderr = common::runtime_error("emojica_spans_operator.emojica", "print 1 + \"a\";\n");
+    assert!(stderr.contains(":1:9\n"), "{}", stderr);
+
+    let stderr = common::runtime_error("emojica_spans_emoji_operator.emojica", "print 1 ➕ \"a\";\n");
+    assert!(stderr.contains(":1:9\n"), "{}", stderr);
+}
+
+#[test]
+fn columns_count_characters_not_bytes() {
+    let stderr = common::runtime_error("emojica_spans_columns.emojica", "var 🍕 = \"é\"; print 🍕 ✖️ 🍕;\n");
+    assert!(stderr.contains(":1:22\n"), "{}", stderr);
+}
+
+#[test]
+fn columns_restart_on_each_line() {
+    let source = "var a = 1;\nvar s = \"two\nlines\"; print -s;\n";
+    let stderr = common::runtime_error("emojica_spans_lines.emojica", source);
+    assert!(stderr.contains(":3:15\n"), "{}", stderr);
+}
+
+#[test]
+fn points_at_the_name() {
+    let stderr = common::runtime_error("emojica_spans_name.emojica", "print 1;\n  print missing;\n");
+    assert!(stderr.contains(":2:9\n"), "{}", stderr);
+}
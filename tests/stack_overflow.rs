@@ -0,0 +1,64 @@
+mod common;
+
+const DEPTH: &str = "fun depth(n) {\n  if (n == 0) return 0;\n  return depth(n - 1) + 1;\n}\n";
+
+fn run_depth(name: &str, calls: usize, flags: &[&str]) -> std::process::Output {
+    let script = common::write_script(name, &format!("{}print depth({});\n", DEPTH, calls));
+    let mut arguments: Vec<&std::ffi::OsStr> = flags.iter().map(|flag| flag.as_ref()).collect();
+    arguments.push(script.as_os_str());
+    common::run_with(arguments)
+}
+
+#[test]
+fn unbounded_recursion_is_a_runtime_error() {
+    let output = common::run_source("emojica_overflow_unbounded.emojica", "fun forever(n) {\n  return forever(n + 1);\n}\nforever(0);\n");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(70), "{}", stderr);
+    assert!(stderr.starts_with("error[E301]: Stack overflow.\n"), "{}", stderr);
+    assert!(stderr.contains("2 |   return forever(n + 1);\n"), "{}", stderr);
+    assert!(stderr.contains(&format!("... {} more calls ...", 10_000 - 14)), "{}", stderr);
+}
+
+#[test]
+fn deep_recursion_within_the_limit_runs() {
+    let output = run_depth("emojica_overflow_deep.emojica", 9_000, &[]);
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "9000\n", "{}", String::from_utf8_lossy(&output.stderr));
+}
+
+#[test]
+fn the_limit_can_be_set_on_the_command_line() {
+    let output = run_depth("emojica_overflow_flag_ok.emojica", 40, &["--max-depth", "50"]);
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "40\n");
+
+    let output = run_depth("emojica_overflow_flag_low.emojica", 60, &["--max-depth", "50"]);
+    assert_eq!(output.status.code(), Some(70));
+    assert!(String::from_utf8_lossy(&output.stderr).starts_with("error[E301]: Stack overflow.\n"));
+
+    let output = run_depth("emojica_overflow_flag_high.emojica", 20_000, &["--max-depth", "30000"]);
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "20000\n", "{}", String::from_utf8_lossy(&output.stderr));
+}
+
+#[test]
+fn the_limit_must_be_a_number() {
+    let output = common::run_with(["--max-depth", "lots"]);
+    assert_eq!(output.status.code(), Some(64));
+}
+
+#[cfg(unix)]
+#[test]
+fn small_scripts_run_under_a_memory_limit() {
+    let script = common::write_script("emojica_overflow_limited.emojica", "print 1;\n");
+    let output = common::run_limited(500_000, &[script.as_os_str()]);
+    assert_eq!(output.status.code(), Some(0), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "1\n");
+}
+
+#[cfg(unix)]
+#[test]
+fn a_stack_too_big_to_reserve_lowers_the_limit() {
+    let script = common::write_script("emojica_overflow_fallback.emojica", &format!("{}print depth(50);\n", DEPTH));
+    let output = common::run_limited(200_000, &["--max-depth".as_ref(), "100000".as_ref(), script.as_os_str()]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.starts_with("Cannot reserve a stack for 100000 nested calls"), "{}", stderr);
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "50\n", "{}", stderr);
+}
@@ -0,0 +1,41 @@
+mod common;
+
+#[test]
+fn argument_errors() {
+    let stderr = common::runtime_error("emojica_stdlib_sqrt.emojica", "sqrt(\"four\");\n");
+    assert!(stderr.contains("Can only take the square root of a number."), "{}", stderr);
+
+    let stderr = common::runtime_error("emojica_stdlib_num.emojica", "num(\"four\");\n");
+    assert!(stderr.contains("Cannot convert 'four' to a number."), "{}", stderr);
+
+    let stderr = common::runtime_error("emojica_stdlib_seed.emojica", "seed(1.5);\n");
+    assert!(stderr.contains("Seed must be an integer."), "{}", stderr);
+
+    let stderr = common::runtime_error("emojica_stdlib_upper.emojica", "upper(1);\n");
+    assert!(stderr.contains("Can only uppercase a string."), "{}", stderr);
+}
+
+#[test]
+fn variadic_arity() {
+    let stderr = common::runtime_error("emojica_stdlib_max.emojica", "max();\n");
+    assert!(stderr.contains("Expected at least 1 argument but got 0."), "{}", stderr);
+
+    let stderr = common::runtime_error("emojica_stdlib_sqrt.emojica", "sqrt(1, 2);\n");
+    assert!(stderr.contains("Expected 1 argument but got 2."), "{}", stderr);
+
+    let stderr = common::runtime_error("emojica_stdlib_max_type.emojica", "max(1, \"2\");\n");
+    assert!(stderr.contains("Can only take the max of a number."), "{}", stderr);
+}
+
+#[test]
+fn callback_errors() {
+    let source = "fun bad(x) {\n  return x + nil;\n}\nmap([1], bad);\n";
+    let stderr = common::runtime_error("emojica_stdlib_callback.emojica", source);
+    assert!(stderr.contains("Operands must be two numbers or two strings."), "{}", stderr);
+
+    let stderr = common::runtime_error("emojica_stdlib_callback_arity.emojica", "fun two(a, b) {}\nmap([1], two);\n");
+    assert!(stderr.contains("Expected 2 arguments but got 1."), "{}", stderr);
+
+    let stderr = common::runtime_error("emojica_stdlib_not_callable.emojica", "map([1], 2);\n");
+    assert!(stderr.contains("Can only call functions and classes."), "{}", stderr);
+}
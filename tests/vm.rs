@@ -0,0 +1,56 @@
+mod common;
+
+fn run_vm(name: &str, source: &str) -> std::process::Output {
+    let script = common::write_script(name, source);
+    common::run_with([std::ffi::OsStr::new("--vm"), script.as_os_str()])
+}
+
+fn backtrace_of(stderr: &str) -> Vec<&str> {
+    stderr.lines().map(str::trim).filter(|line| line.starts_with("at ") || line.starts_with("... ")).collect()
+}
+
+#[test]
+fn closures_share_captured_variables() {
+    let source = "fun pair() {\n  var n = 0;\n  fun inc() { n = n + 1; }\n  fun get() { return n; }\n  return [inc, get];\n}\nvar p = pair();\np[0]();\np[0]();\nprint p[1]();\n";
+    let output = run_vm("emojica_vm_closures.emojica", source);
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "2\n", "{}", String::from_utf8_lossy(&output.stderr));
+}
+
+#[test]
+fn runtime_errors_have_the_same_backtrace() {
+    let source = "class Box {\n  init(f) { this.items = map([1], f); }\n}\nfun bad(x) {\n  return x + nil;\n}\nBox(bad);\n";
+    let script = common::write_script("emojica_vm_backtrace.emojica", source);
+    let walked = common::run(&script);
+    let compiled = common::run_with([std::ffi::OsStr::new("--vm"), script.as_os_str()]);
+    assert_eq!(compiled.status.code(), Some(70));
+    let stderr = String::from_utf8_lossy(&compiled.stderr);
+    assert_eq!(stderr, String::from_utf8_lossy(&walked.stderr));
+    assert_eq!(backtrace_of(&stderr).len(), 4, "{}", stderr);
+}
+
+#[test]
+fn unbounded_recursion_overflows() {
+    let output = run_vm("emojica_vm_overflow.emojica", "fun forever(n) {\n  return forever(n + 1);\n}\nforever(0);\n");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(70), "{}", stderr);
+    assert!(stderr.starts_with("error[E301]: Stack overflow.\n"), "{}", stderr);
+    assert!(stderr.contains(&format!("... {} more calls ...", 10_000 - 14)), "{}", stderr);
+}
+
+#[test]
+fn natives_calling_back_overflow_cleanly() {
+    let source = "fun f(n) {\n  if (n == 0) return 0;\n  return map([n - 1], f)[0] + 1;\n}\nprint f(5000);\n";
+    let output = run_vm("emojica_vm_callbacks.emojica", source);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(70), "{}", stderr);
+    assert!(stderr.starts_with("error[E301]: Stack overflow.\n"), "{}", stderr);
+}
+
+#[cfg(unix)]
+#[test]
+fn deep_recursion_needs_no_big_stack() {
+    let source = "fun depth(n) {\n  if (n == 0) return 0;\n  return depth(n - 1) + 1;\n}\nprint depth(9000);\n";
+    let script = common::write_script("emojica_vm_limited.emojica", source);
+    let output = common::run_limited(100_000, &["--vm".as_ref(), script.as_os_str()]);
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "9000\n", "{}", String::from_utf8_lossy(&output.stderr));
+}